@@ -0,0 +1,70 @@
+//! Compares tracing primary rays one at a time against tracing them as
+//! packets. Run with `cargo bench --bench packet_traversal`.
+
+use std::{
+    array,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use raytrace::{
+    camera::Camera,
+    example_worlds::{MeshDetail, cornell_box, room},
+    hittables::{Hittable, PacketHits},
+    primitives::{Float, PACKET_WIDTH, Ray, RayPacket, interval},
+};
+
+// how many packets of samples to trace through each pixel
+const PACKETS_PER_PIXEL: usize = 2;
+
+fn bench(name: &str, world: &dyn Hittable, cam: &Camera) {
+    let mut scalar = Duration::ZERO;
+    let mut packet = Duration::ZERO;
+    let mut hits = (0, 0);
+
+    for y in 0..cam.image_height() {
+        // generate the rays up front so only traversal is timed
+        let row: Vec<[Ray; PACKET_WIDTH]> = (0..cam.image_width())
+            .flat_map(|x| (0..PACKETS_PER_PIXEL).map(move |_| x))
+            .map(|x| array::from_fn(|_| cam.get_ray(x, y)))
+            .collect();
+
+        let start = Instant::now();
+        for rays in &row {
+            for r in rays {
+                hits.0 +=
+                    usize::from(black_box(world.hit(r, &interval(0.0, Float::INFINITY))).is_some());
+            }
+        }
+        scalar += start.elapsed();
+
+        let packets: Vec<RayPacket> = row.iter().map(RayPacket::new).collect();
+        let start = Instant::now();
+        for rays in &packets {
+            let mut packet_hits = PacketHits::new(Float::INFINITY);
+            world.hit_packet(rays, 0.0, [true; PACKET_WIDTH], &mut packet_hits);
+            hits.1 += black_box(packet_hits).recs.iter().flatten().count();
+        }
+        packet += start.elapsed();
+    }
+
+    assert_eq!(
+        hits.0, hits.1,
+        "{name}: packet tracing found different hits"
+    );
+
+    println!(
+        "{name}: scalar {:.3}s, packet {:.3}s ({:.2}x)",
+        scalar.as_secs_f64(),
+        packet.as_secs_f64(),
+        scalar.as_secs_f64() / packet.as_secs_f64()
+    );
+}
+
+fn main() {
+    let (world, _, cam) = cornell_box();
+    bench("cornell_box", &world, &cam);
+
+    let (world, cam) = room(&MeshDetail::default());
+    bench("room", &world, &cam);
+}
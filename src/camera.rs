@@ -1,5 +1,6 @@
 use rayon::prelude::*;
 use std::{
+    array,
     error::Error,
     sync::{
         Arc,
@@ -8,13 +9,21 @@ use std::{
 };
 
 use crate::{
-    hittables::Hittable,
+    hittables::{HitRecord, Hittable, PacketHits},
     image_writer::ImageWriter,
-    misc::rand_f64,
-    pdfs::{CosinePdf, HittablePdf, MixturePdf, Pdf},
-    primitives::{Color, Onb, Point3, Ray, Vec3, color, interval, point3, ray, vec3},
+    materials::ScatterRecord,
+    misc::rand_float,
+    pdfs::{HittablePdf, MixturePdf, Pdf},
+    primitives::{
+        Color, Float, PACKET_WIDTH, PathState, Point3, Ray, RayPacket, SampledWavelengths, Vec3,
+        color, interval, point3, ray, vec3,
+    },
+    tern,
 };
 
+/// The most times light is scattered inside a medium before it's given up on.
+const MAX_SCATTERING: u32 = 256;
+
 fn opt_assert(cond: bool) -> Option<()> {
     cond.then_some(())
 }
@@ -22,7 +31,7 @@ fn opt_assert(cond: bool) -> Option<()> {
 #[derive(Debug, Clone, Copy)]
 pub struct CameraOptions {
     /// The aspect ratio of the output image.
-    pub aspect_ratio: f64,
+    pub aspect_ratio: Float,
     /// The width of the output image.
     pub image_width: usize,
     /// The number of rays to sample per pixel.
@@ -30,7 +39,7 @@ pub struct CameraOptions {
     /// The maximum depth a ray is allowed to search.
     pub max_depth: u32,
     /// The field of view in degrees.
-    pub v_fov: f64,
+    pub v_fov: Float,
     /// The origin of the camera.
     pub look_from: Point3,
     /// The target of the camera.
@@ -38,11 +47,17 @@ pub struct CameraOptions {
     /// A vector representing the upwards direction.
     pub vup: Vec3,
     /// The angle of the defocus cone.
-    pub defocus_angle: f64,
+    pub defocus_angle: Float,
     /// The distance where objects are perfectly in focus.
-    pub focus_dist: f64,
+    pub focus_dist: Float,
     /// The default color if a ray doesn't collide with anything.
     pub background: Color,
+    /// Whether to trace the samples of each pixel as ray packets for the
+    /// first bounce. Off by default.
+    pub packet_tracing: bool,
+    /// Whether to trace sampled wavelengths of light instead of RGB, which
+    /// lets dispersive dielectrics split light into its colors.
+    pub spectral: bool,
 }
 
 impl Default for CameraOptions {
@@ -59,6 +74,8 @@ impl Default for CameraOptions {
             defocus_angle: 0.0,
             focus_dist: 10.0,
             background: color(0.0, 0.0, 0.0),
+            packet_tracing: false,
+            spectral: false,
         }
     }
 }
@@ -72,10 +89,12 @@ pub struct Camera {
     pixel_00_loc: Point3,
     pixel_delta_u: Vec3,
     pixel_delta_v: Vec3,
-    defocus_angle: f64,
+    defocus_angle: Float,
     defocus_disk_u: Vec3,
     defocus_disk_v: Vec3,
     background: Color,
+    packet_tracing: bool,
+    spectral: bool,
 }
 
 impl Camera {
@@ -93,8 +112,10 @@ impl Camera {
             defocus_angle,
             focus_dist,
             background,
+            packet_tracing,
+            spectral,
         } = options;
-        let image_height = (image_width as f64 / aspect_ratio).round() as usize;
+        let image_height = (image_width as Float / aspect_ratio).round() as usize;
 
         // ensure dimensions are greater than 0.
         opt_assert(aspect_ratio > 0.0)?;
@@ -104,7 +125,7 @@ impl Camera {
         let theta = v_fov.to_radians();
         let h = (theta / 2.0).tan();
         let viewport_height = 2.0 * h * focus_dist;
-        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
+        let viewport_width = viewport_height * (image_width as Float / image_height as Float);
 
         let w = (look_from - look_at).unit_vector();
         let u = vup.cross(w);
@@ -113,8 +134,8 @@ impl Camera {
         let viewport_u = u * viewport_width;
         let viewport_v = -v * viewport_height;
 
-        let pixel_delta_u = viewport_u / image_width as f64;
-        let pixel_delta_v = viewport_v / image_height as f64;
+        let pixel_delta_u = viewport_u / image_width as Float;
+        let pixel_delta_v = viewport_v / image_height as Float;
 
         let viewport_upper_left =
             look_from - (w * focus_dist) - viewport_u / 2.0 - viewport_v / 2.0;
@@ -137,21 +158,80 @@ impl Camera {
             defocus_disk_u,
             defocus_disk_v,
             background,
+            packet_tracing,
+            spectral,
         })
     }
 
+    #[must_use]
+    pub fn image_width(&self) -> usize {
+        self.image_width
+    }
+
+    #[must_use]
+    pub fn image_height(&self) -> usize {
+        self.image_height
+    }
+
     // Renders a scanline into Vec of colors
+    // (the sample count converts losslessly only without the `f32` feature)
+    #[allow(clippy::cast_lossless)]
     pub fn scanline(&self, world: &dyn Hittable, lights: &dyn Hittable, y: usize) -> Vec<Color> {
         (0..self.image_width)
             .map(|x| {
-                (0..self.samples_per_pixel)
-                    .map(|_| self.ray_color(&self.get_ray(x, y), self.max_depth, world, lights))
-                    .sum::<Color>()
-                    / f64::from(self.samples_per_pixel)
+                let total = if self.packet_tracing {
+                    self.pixel_color_packets(x, y, world, lights)
+                } else {
+                    (0..self.samples_per_pixel)
+                        .map(|_| {
+                            let (r, path) = (self.get_ray(x, y), self.start_path());
+                            let radiance = self.ray_color(&r, &path, self.max_depth, world, lights);
+                            film_color(&path, radiance)
+                        })
+                        .sum::<Color>()
+                };
+                total / self.samples_per_pixel as Float
             })
             .collect()
     }
 
+    // Sums the samples of a pixel, finding their first hits a packet at a
+    // time. The samples of one pixel are nearly identical rays, so they're as
+    // coherent as it gets. Later bounces diverge and are traced one by one.
+    fn pixel_color_packets(
+        &self,
+        x: usize,
+        y: usize,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+    ) -> Color {
+        if self.max_depth == 0 {
+            return color(0.0, 0.0, 0.0);
+        }
+
+        let mut total = color(0.0, 0.0, 0.0);
+        let mut remaining = self.samples_per_pixel as usize;
+
+        while remaining > 0 {
+            let lanes = remaining.min(PACKET_WIDTH);
+            let rays: [Ray; PACKET_WIDTH] = array::from_fn(|_| self.get_ray(x, y));
+            let active = array::from_fn(|i| i < lanes);
+
+            let mut hits = PacketHits::new(Float::INFINITY);
+            world.hit_packet(&RayPacket::new(&rays), 0.0, active, &mut hits);
+
+            for (r, rec) in rays.iter().zip(hits.recs).take(lanes) {
+                let path = self.start_path();
+                let radiance = self.shade(r, &path, rec, self.max_depth, world, lights);
+                total += film_color(&path, radiance);
+            }
+
+            remaining -= lanes;
+        }
+
+        total
+    }
+
     // Renders a hittable into a 2d array of colors
     pub fn render(&self, world: &dyn Hittable, lights: &dyn Hittable) -> Vec<Vec<Color>> {
         // AtomicUsize is faster than Mutex
@@ -166,7 +246,7 @@ impl Camera {
                 println!(
                     "starting {prev} / {} ({:.2}%)",
                     self.image_height,
-                    prev as f64 / self.image_height as f64 * 100.0
+                    prev as Float / self.image_height as Float * 100.0
                 );
                 self.scanline(world, lights, y)
             })
@@ -193,42 +273,129 @@ impl Camera {
 
     // Gets the final color of a ray through a given world. Recursively calls
     // itself for scattered rays
-    fn ray_color(&self, r: &Ray, depth: u32, world: &dyn Hittable, lights: &dyn Hittable) -> Color {
+    fn ray_color(
+        &self,
+        r: &Ray,
+        path: &PathState,
+        depth: u32,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+    ) -> Color {
         // if we hit the bounce limit, no more light is gathered
         if depth == 0 {
             return color(0.0, 0.0, 0.0);
         }
 
-        if let Some(rec) = world.hit(r, &interval(0.001, f64::INFINITY)) {
-            let emitted = rec.mat.emitted(r, &rec, rec.u, rec.v, rec.p);
-
-            if let Some((attenuation, scattered, pdf_value)) = rec.mat.scatter(r, &rec) {
-                let p0 = HittablePdf::new(lights, rec.p);
-                let p1 = CosinePdf::new(Onb::new(rec.normal));
-                let mixed_pdf = MixturePdf::new(&p0, &p1);
-
-                let scattered = ray(rec.p, mixed_pdf.generate(), r.time);
-                let pdf_value = mixed_pdf.value(scattered.dir);
+        // light wandering through a medium that scatters it doesn't count
+        // against the depth, or a thick one would look darker than it is.
+        // every step of the walk is sampled for the same channel
+        let channel = ((rand_float() * 3.0) as usize).min(2);
+        let mut walk: Option<Ray> = None;
+        let mut weight = color(1.0, 1.0, 1.0);
+        let mut pdf = color(1.0, 1.0, 1.0);
+        for _ in 0..MAX_SCATTERING {
+            let r = walk.as_ref().unwrap_or(r);
+
+            // scattered rays start just off the surface they leave, so there's
+            // no need for a minimum distance to avoid shadow acne
+            let rec = world.hit(r, &interval(0.0, Float::INFINITY));
+
+            // whatever the ray is inside of absorbs light on its way back, and
+            // may scatter it before it gets there
+            let distance = rec
+                .as_ref()
+                .map_or(Float::INFINITY, |rec| rec.t * r.dir.length());
+            let sample =
+                path.media
+                    .current()
+                    .sample_scattering(distance, path.wavelengths, channel);
+
+            // only the ratio matters, so both are kept from growing too large
+            // or small over a long walk
+            let scale = sample.pdf.average();
+            if scale <= 0.0 {
+                break;
+            }
+            weight = weight * sample.weight / scale;
+            pdf = pdf * sample.pdf / scale;
+
+            let Some(scattered_at) = sample.distance else {
+                let throughput = weight / pdf.average();
+                return throughput * self.shade(r, path, rec, depth, world, lights);
+            };
+            let p = r.at(scattered_at / r.dir.length());
+            walk = Some(ray(p, Vec3::random_unit_vector(), r.time));
+        }
 
-                let scattering_pdf = rec.mat.scattering_pdf(r, &rec, &scattered);
+        color(0.0, 0.0, 0.0)
+    }
 
-                let color_sample = self.ray_color(&scattered, depth - 1, world, lights);
+    // Gets the color of a ray given what it hit
+    fn shade(
+        &self,
+        r: &Ray,
+        path: &PathState,
+        rec: Option<HitRecord>,
+        depth: u32,
+        world: &dyn Hittable,
+        lights: &dyn Hittable,
+    ) -> Color {
+        if let Some(mut rec) = rec {
+            if let Some(normal) = rec.mat.perturbed_normal(&rec) {
+                rec = rec.with_shading_normal(normal);
+            }
 
-                let color_from_scatter = (attenuation * scattering_pdf * color_sample) / pdf_value;
-                emitted + color_from_scatter
-            } else {
-                emitted
+            let emitted = rec.mat.emitted(r, &rec, rec.u, rec.v, rec.p, path);
+
+            let mut scattered_path = *path;
+            let scatter = rec.mat.scatter(r, &rec, &mut scattered_path);
+            let termination = termination_weight(path, &scattered_path);
+            match scatter {
+                Some(ScatterRecord::Pdf { attenuation, pdf }) => {
+                    let light_pdf = HittablePdf::new(lights, rec.p);
+                    let mixed_pdf = MixturePdf::new(&light_pdf, &*pdf);
+
+                    let dir = mixed_pdf.generate();
+                    if dir.is_near_zero() {
+                        return emitted;
+                    }
+
+                    let scattered = rec.spawn_ray(dir, r.time);
+                    let pdf_value = mixed_pdf.value(scattered.dir);
+                    if pdf_value <= 0.0 {
+                        return emitted;
+                    }
+
+                    let scattering = rec
+                        .mat
+                        .scattering_color(r, &rec, &scattered, &scattered_path);
+
+                    let next = continue_path(&scattered_path, &rec, &scattered);
+                    let color_sample = self.ray_color(&scattered, &next, depth - 1, world, lights);
+
+                    let color_from_scatter =
+                        (attenuation * termination * scattering * color_sample) / pdf_value;
+                    emitted + color_from_scatter
+                }
+                Some(ScatterRecord::SkipPdf { attenuation, ray }) => {
+                    let next = continue_path(&scattered_path, &rec, &ray);
+                    let color_sample = self.ray_color(&ray, &next, depth - 1, world, lights);
+                    emitted + attenuation * termination * color_sample
+                }
+                None => emitted,
             }
         } else {
-            self.background
+            path.spectrum(self.background)
         }
     }
 
-    fn get_ray(&self, x: usize, y: usize) -> Ray {
+    /// Samples a ray through pixel `(x, y)`.
+    #[must_use]
+    pub fn get_ray(&self, x: usize, y: usize) -> Ray {
         let offset = sample_square();
         let pixel_sample = self.pixel_00_loc
-            + (self.pixel_delta_u * (x as f64 + offset.x))
-            + (self.pixel_delta_v * (y as f64 + offset.y));
+            + (self.pixel_delta_u * (x as Float + offset.x))
+            + (self.pixel_delta_v * (y as Float + offset.y));
 
         let ray_origin = if self.defocus_angle <= 0.0 {
             self.look_from
@@ -237,7 +404,18 @@ impl Camera {
         };
         let ray_direction = pixel_sample - ray_origin;
 
-        ray(ray_origin, ray_direction, rand_f64())
+        ray(ray_origin, ray_direction, rand_float())
+    }
+
+    /// The state a camera ray's path starts out with, tracing a random set
+    /// of wavelengths in spectral mode.
+    fn start_path(&self) -> PathState {
+        let path = PathState::default();
+        tern!(
+            self.spectral,
+            path.with_wavelengths(SampledWavelengths::sample_visible(rand_float())),
+            path
+        )
     }
 
     fn defocus_disk_sample(&self) -> Point3 {
@@ -246,6 +424,38 @@ impl Camera {
     }
 }
 
+/// The path `scattered` continues, entering or leaving what fills the
+/// surface if it passes through it.
+fn continue_path(path: &PathState, rec: &HitRecord, scattered: &Ray) -> PathState {
+    let media = match rec.mat.interior() {
+        Some(medium) if !rec.is_reflection(scattered.dir) => tern!(
+            rec.front_face,
+            path.media.pushed(medium),
+            path.media.popped(medium)
+        ),
+        _ => path.media,
+    };
+    PathState { media, ..*path }
+}
+
+/// When a material stops tracing all but the hero wavelength, the hero
+/// carries the light of the others from then on.
+fn termination_weight(path: &PathState, scattered: &PathState) -> Color {
+    match (path.wavelengths, scattered.wavelengths) {
+        (Some(before), Some(after))
+            if !before.secondary_terminated() && after.secondary_terminated() =>
+        {
+            color(3.0, 0.0, 0.0)
+        }
+        _ => color(1.0, 1.0, 1.0),
+    }
+}
+
+/// The RGB color of the light a camera ray brought back.
+fn film_color(path: &PathState, radiance: Color) -> Color {
+    path.wavelengths.map_or(radiance, |w| w.to_rgb(radiance))
+}
+
 fn sample_square() -> Vec3 {
-    vec3(rand_f64() - 0.5, rand_f64() - 0.5, 0.0)
+    vec3(rand_float() - 0.5, rand_float() - 0.5, 0.0)
 }
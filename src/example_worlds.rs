@@ -3,14 +3,19 @@ use std::sync::Arc;
 use crate::{
     camera::{Camera, CameraOptions},
     hittables::{
-        BvhNode, HittableList, Quad, constant_medium_from_color, cube, moving, quad, rotate_y, sphere, translate, triangle
+        BvhNode, HittableList, Quad, TriangleMesh, constant_medium_from_color, cube, moving, plane,
+        quad, rotate_y, sphere, translate, triangle,
     },
     materials::{
-        Material, dielectric, diffuse_light_from_color, lambertian, lambertian_from_color, metal,
+        Material, Principled, dielectric, diffuse_light_from_color, lambertian,
+        lambertian_from_color, metal,
+    },
+    misc::rand_float,
+    primitives::{Color, Float, Point3, color, point3, vec3},
+    textures::{
+        ImageTexture, Texture, TextureWrap, checker_texture_from_colors, image_texture_from_bytes,
+        noise_texture,
     },
-    misc::rand_f64,
-    primitives::{Color, Point3, color, point3, vec3},
-    textures::{checker_texture_from_colors, image_texture_from_bytes, noise_texture},
 };
 
 #[must_use]
@@ -20,10 +25,14 @@ pub fn simple() -> (HittableList, Camera) {
     let mat_ground = lambertian_from_color(color(0.8, 0.8, 0.0));
     let mat_center = lambertian_from_color(color(0.1, 0.2, 0.5));
     let mat_left = dielectric(1.50);
-    let mat_bubble = dielectric(1.00 / 1.50);
+    let mat_bubble = dielectric(1.00);
     let mat_right = metal(color(0.8, 0.8, 0.8), 0.5);
 
-    world.add(sphere(point3(0.0, -100.5, -1.0), 100.0, mat_ground));
+    world.add(plane(
+        point3(0.0, -0.5, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        mat_ground,
+    ));
     world.add(sphere(point3(0.0, 0.0, -1.2), 0.5, mat_center));
     world.add(sphere(point3(-1.0, 0.0, -1.0), 0.5, mat_left));
     world.add(sphere(point3(-1.0, 0.0, -1.0), 0.4, mat_bubble));
@@ -54,18 +63,22 @@ pub fn bouncing_spheres() -> (BvhNode, Camera) {
         color(0.9, 0.9, 0.9),
     ));
 
-    world.add(sphere(point3(0.0, -1000.0, 0.0), 1000.0, ground_material));
+    world.add(plane(
+        point3(0.0, 0.0, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        ground_material,
+    ));
 
-    for a in -11..11 {
-        for b in -11..11 {
+    for a in -11_i16..11 {
+        for b in -11_i16..11 {
             let center = point3(
-                f64::from(a) + 0.9 * rand_f64(),
+                Float::from(a) + 0.9 * rand_float(),
                 0.2,
-                f64::from(b) + 0.9 * rand_f64(),
+                Float::from(b) + 0.9 * rand_float(),
             );
 
             if (center - point3(4.0, 0.2, 0.0)).length() > 0.9 {
-                match rand_f64() {
+                match rand_float() {
                     // diffuse
                     0.0..0.8 => {
                         let albedo = Color::random() * Color::random();
@@ -353,6 +366,7 @@ pub fn cornell_box() -> (HittableList, Quad, Camera) {
         look_from: point3(278.0, 278.0, -800.0),
         look_at: point3(278.0, 278.0, 0.0),
         background: color(0.0, 0.0, 0.0),
+        spectral: true,
         ..Default::default()
     })
     .unwrap();
@@ -388,44 +402,124 @@ pub fn triangles() -> (HittableList, Camera) {
     (world, cam)
 }
 
+/// The `Principled` material a glTF material describes, with its textures
+/// taken from the `images` decoded on import. Meshes only load the first set
+/// of texture coordinates, so textures using another set are left out, as
+/// are ones that aren't 8-bit RGB or RGBA. The factors they'd scale are used
+/// alone instead.
+fn gltf_material(material: &gltf::Material, images: &[gltf::image::Data]) -> Principled {
+    let rgb = |c: [f32; 3]| color(Float::from(c[0]), Float::from(c[1]), Float::from(c[2]));
+    let texture = |info: gltf::texture::Info| -> Option<Arc<dyn Texture>> {
+        if info.tex_coord() != 0 {
+            return None;
+        }
+
+        let image = &images[info.texture().source().index()];
+        let bytes_per_pixel = match image.format {
+            gltf::image::Format::R8G8B8 => 3,
+            gltf::image::Format::R8G8B8A8 => 4,
+            _ => return None,
+        };
+        let wrap = |mode| match mode {
+            gltf::texture::WrappingMode::ClampToEdge => TextureWrap::Clamp,
+            gltf::texture::WrappingMode::MirroredRepeat => TextureWrap::MirroredRepeat,
+            gltf::texture::WrappingMode::Repeat => TextureWrap::Repeat,
+        };
+        let sampler = info.texture().sampler();
+        Some(Arc::new(
+            ImageTexture::from_pixels(
+                image.pixels.clone(),
+                image.width,
+                image.height,
+                bytes_per_pixel,
+            )
+            .with_wrap(wrap(sampler.wrap_s()), wrap(sampler.wrap_t())),
+        ))
+    };
+
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+    let mut mat = Principled::new(
+        rgb([r, g, b]),
+        Float::from(pbr.metallic_factor()),
+        Float::from(pbr.roughness_factor()),
+    )
+    .with_emission(
+        rgb(material.emissive_factor()),
+        material.emissive_strength().map_or(1.0, Float::from),
+    );
+
+    if let Some(tex) = pbr.base_color_texture().and_then(texture) {
+        mat = mat.with_base_color_texture(tex);
+    }
+    if let Some(tex) = pbr.metallic_roughness_texture().and_then(texture) {
+        mat = mat.with_metallic_roughness_texture(tex);
+    }
+    if let Some(specular) = material.specular() {
+        mat = mat.with_specular(
+            Float::from(specular.specular_factor()),
+            rgb(specular.specular_color_factor()),
+        );
+        if let Some(tex) = specular.specular_color_texture().and_then(texture) {
+            mat = mat.with_specular_color_texture(tex);
+        }
+    }
+    if let Some(transmission) = material.transmission() {
+        mat = mat.with_transmission(Float::from(transmission.transmission_factor()));
+        if let Some(tex) = transmission.transmission_texture().and_then(texture) {
+            mat = mat.with_transmission_texture(tex);
+        }
+    }
+    if let Some(ior) = material.ior() {
+        mat = mat.with_ior(Float::from(ior));
+    }
+    if let Some(tex) = material.emissive_texture().and_then(texture) {
+        mat = mat.with_emissive_texture(tex);
+    }
+
+    mat
+}
+
+/// How much finer to make meshes loaded from files before tracing them.
+#[derive(Clone, Default)]
+pub struct MeshDetail {
+    /// Rounds of Loop subdivision smoothing each mesh.
+    pub subdivision: u32,
+    /// A texture whose brightness moves the surface along its normals, how
+    /// far the brightest parts move, and how many times the triangles are
+    /// split to show it.
+    pub displacement: Option<(Arc<dyn Texture>, Float, u32)>,
+}
+
+impl MeshDetail {
+    fn apply(&self, mesh: TriangleMesh) -> TriangleMesh {
+        let mesh = mesh.subdivide(self.subdivision, &[]);
+        match &self.displacement {
+            Some((height, scale, levels)) => mesh.displace(height.as_ref(), *scale, *levels),
+            None => mesh,
+        }
+    }
+}
+
 #[must_use]
-pub fn room() -> (BvhNode, Camera) {
-    let (gltf, buffers, _) = gltf::import("src/res/room.gltf").unwrap();
+pub fn room(detail: &MeshDetail) -> (BvhNode, Camera) {
+    let (gltf, buffers, images) = gltf::import("src/res/room.gltf").unwrap();
 
     let mut world = HittableList::default();
 
-    let materials: [Arc<dyn Material>; 8] = [
-        lambertian_from_color(color(
-            0.137_254_998_087_883,
-            0.137_254_998_087_883,
-            0.137_254_998_087_883,
-        )),
-        lambertian_from_color(color(
-            0.227_450_996_637_344_36,
-            0.227_450_996_637_344_36,
-            0.227_450_996_637_344_36,
-        )),
-        lambertian_from_color(color(
-            0.200_000_002_980_232_24,
-            0.200_000_002_980_232_24,
-            0.200_000_002_980_232_24,
-        )),
-        lambertian_from_color(color(0.7, 0.7, 0.7)),
-        lambertian(image_texture_from_bytes(include_bytes!("./res/Wood_Floor_Light.png")).unwrap()),
-        lambertian(image_texture_from_bytes(include_bytes!("./res/Wood_Floor.png")).unwrap()),
-        diffuse_light_from_color(
-            color(
-                0.392_156_988_382_339_5,
-                0.584_313_988_685_607_9,
-                0.929_412_007_331_848_1,
-            ) * 15.0,
-        ),
-        lambertian_from_color(color(
-            0.117_646_999_657_154_08,
-            0.117_646_999_657_154_08,
-            0.117_646_999_657_154_08,
-        )),
-    ];
+    let materials: Vec<Arc<dyn Material>> = gltf
+        .materials()
+        .map(|material| -> Arc<dyn Material> {
+            // the room has no lights of its own, so its blue glass is made
+            // to glow instead
+            if material.name() == Some("Translucent_Glass_Blue") {
+                let [r, g, b, _] = material.pbr_metallic_roughness().base_color_factor();
+                let glass = color(Float::from(r), Float::from(g), Float::from(b));
+                return diffuse_light_from_color(glass * 15.0);
+            }
+            Arc::new(gltf_material(&material, &images))
+        })
+        .collect();
 
     // assuming only one scene, one node, no children, and one mesh
     let mesh = gltf.meshes().next().unwrap();
@@ -434,25 +528,38 @@ pub fn room() -> (BvhNode, Camera) {
         let positions = reader
             .read_positions()
             .unwrap()
-            .map(|x| Point3::new(f64::from(x[0]), f64::from(x[1]), f64::from(x[2])))
+            .map(|x| Point3::new(Float::from(x[0]), Float::from(x[1]), Float::from(x[2])))
+            .collect::<Vec<_>>();
+        let normals = reader
+            .read_normals()
+            .unwrap()
+            .map(|n| vec3(Float::from(n[0]), Float::from(n[1]), Float::from(n[2])))
+            .collect::<Vec<_>>();
+        // glTF puts the origin of texture space at the top left of the image,
+        // while our textures put it at the bottom left
+        let uvs = reader
+            .read_tex_coords(0)
+            .unwrap()
+            .into_f32()
+            .map(|uv| (Float::from(uv[0]), 1.0 - Float::from(uv[1])))
             .collect::<Vec<_>>();
         let indices = reader
             .read_indices()
             .unwrap()
             .into_u32()
-            .map(|x| x as usize)
+            .collect::<Vec<_>>()
+            .chunks_exact(3)
+            .map(|tri| [tri[0], tri[1], tri[2]])
             .collect::<Vec<_>>();
 
         let mat_idx = primitive.material().index().unwrap();
 
-        for tri in indices.chunks(3) {
-            if tri.len() == 3 {
-                let p1 = positions[tri[0]];
-                let p2 = positions[tri[1]];
-                let p3 = positions[tri[2]];
-                let t = triangle(p1, p2, p3, materials[mat_idx].clone());
-                world.add(t);
-            }
+        let mesh = TriangleMesh::new(positions, indices, materials[mat_idx].clone())
+            .with_normals(normals)
+            .with_uvs(uvs);
+        let mesh = detail.apply(mesh);
+        for tri in mesh.into_triangles() {
+            world.add(tri);
         }
     }
 
@@ -481,8 +588,8 @@ pub fn book_2_final() -> (HittableList, Camera) {
 
     let ground = lambertian_from_color(color(0.48, 0.83, 0.53));
 
-    for i in (0..BOXES_PER_SIDE).map(f64::from) {
-        for j in (0..BOXES_PER_SIDE).map(f64::from) {
+    for i in (0..BOXES_PER_SIDE).map(Float::from) {
+        for j in (0..BOXES_PER_SIDE).map(Float::from) {
             let w = 100.0;
             let x0 = -1000.0 + i * w;
             let z0 = -1000.0 + j * w;
@@ -1,8 +1,8 @@
-use std::{fmt, sync::Arc};
+use std::{array, fmt, sync::Arc};
 
-use crate::primitives::{Aabb, Interval, Ray, interval};
+use crate::primitives::{Aabb, Float, Interval, PacketMask, Ray, RayPacket, interval};
 
-use super::{HitRecord, Hittable, HittableList};
+use super::{HitRecord, Hittable, HittableList, PacketHits};
 
 pub struct BvhNode {
     left: Arc<dyn Hittable>,
@@ -36,9 +36,36 @@ impl BvhNode {
         Self { left, right, bbox }
     }
 
+    /// Builds a tree over the objects in `list`. Unbounded objects like
+    /// planes would make the box of every node above them infinite, so they
+    /// are kept out of the tree and tested alongside it at the root instead.
     #[must_use]
-    pub fn from_hittable_list(mut list: HittableList) -> Self {
-        Self::new(&mut list.objects)
+    pub fn from_hittable_list(list: HittableList) -> Self {
+        let (mut bounded, unbounded): (Vec<_>, Vec<_>) = list
+            .objects
+            .into_iter()
+            .partition(|object| object.bounding_box().is_bounded());
+
+        if unbounded.is_empty() {
+            return Self::new(&mut bounded);
+        }
+
+        let mut planes = HittableList::default();
+        for object in unbounded {
+            planes.add(object);
+        }
+
+        let tree: Arc<dyn Hittable> = match bounded.len() {
+            0 => Arc::new(HittableList::default()),
+            1 => bounded.remove(0),
+            _ => Arc::new(Self::new(&mut bounded)),
+        };
+
+        Self {
+            bbox: Aabb::from_boxes(planes.bounding_box(), tree.bounding_box()),
+            left: Arc::new(planes),
+            right: tree,
+        }
     }
 }
 
@@ -60,6 +87,25 @@ impl Hittable for BvhNode {
         hit_left.or(hit_right)
     }
 
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        let in_box = self.bbox.hit_packet(rays, t_min, &hits.t_max);
+        let active = array::from_fn(|i| active[i] && in_box[i]);
+
+        // only descend while at least one lane is still inside the box
+        if !active.contains(&true) {
+            return;
+        }
+
+        self.right.hit_packet(rays, t_min, active, hits);
+        self.left.hit_packet(rays, t_min, active, hits);
+    }
+
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
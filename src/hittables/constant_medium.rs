@@ -5,8 +5,8 @@ use std::{
 
 use crate::{
     materials::{Isotropic, Material},
-    misc::rand_f64,
-    primitives::{Color, Interval, Ray, interval, vec3},
+    misc::rand_float,
+    primitives::{Color, Float, Interval, Ray, interval, vec3},
     textures::Texture,
 };
 
@@ -14,19 +14,19 @@ use super::{HitRecord, Hittable};
 
 pub struct ConstantMedium {
     boundary: Arc<dyn Hittable>,
-    neg_inv_density: f64,
+    neg_inv_density: Float,
     phase_function: Arc<dyn Material>,
 }
 
 impl ConstantMedium {
-    pub fn new(boundary: Arc<dyn Hittable>, density: f64, tex: Arc<dyn Texture>) -> Self {
+    pub fn new(boundary: Arc<dyn Hittable>, density: Float, tex: Arc<dyn Texture>) -> Self {
         Self {
             boundary,
             neg_inv_density: -1.0 / density,
             phase_function: Arc::new(Isotropic::new(tex)),
         }
     }
-    pub fn from_color(boundary: Arc<dyn Hittable>, density: f64, albedo: Color) -> Self {
+    pub fn from_color(boundary: Arc<dyn Hittable>, density: Float, albedo: Color) -> Self {
         Self {
             boundary,
             neg_inv_density: -1.0 / density,
@@ -40,10 +40,12 @@ impl Hittable for ConstantMedium {
         // if we never hit the boundary, return
         let mut rec1 = self.boundary.hit(r, &Interval::full())?;
 
-        // find the exit point of the ray
-        let mut rec2 = self
-            .boundary
-            .hit(r, &interval(rec1.t + 0.0001, f64::INFINITY))?;
+        // find the exit point of the ray, continuing on from the entry point
+        let mut rec2 = self.boundary.hit(
+            &rec1.spawn_ray(r.dir, r.time),
+            &interval(0.0, Float::INFINITY),
+        )?;
+        rec2.t += rec1.t;
 
         if rec1.t < ray_t.min {
             rec1.t = ray_t.min;
@@ -62,7 +64,7 @@ impl Hittable for ConstantMedium {
 
         let ray_length = r.dir.length();
         let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-        let hit_distance = self.neg_inv_density * rand_f64().ln();
+        let hit_distance = self.neg_inv_density * rand_float().ln();
 
         if hit_distance > distance_inside_boundary {
             return None;
@@ -98,7 +100,7 @@ impl Display for ConstantMedium {
 
 pub fn constant_medium_from_color(
     boundary: Arc<dyn Hittable>,
-    density: f64,
+    density: Float,
     albedo: Color,
 ) -> Arc<ConstantMedium> {
     Arc::new(ConstantMedium::from_color(boundary, density, albedo))
@@ -0,0 +1,182 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::primitives::{Aabb, Float, Interval, Ray, interval};
+
+use super::{HitRecord, Hittable};
+
+/// Everything inside either of two closed objects.
+pub struct Union {
+    a: Arc<dyn Hittable>,
+    b: Arc<dyn Hittable>,
+    bbox: Aabb,
+}
+
+impl Union {
+    pub fn new(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        let bbox = Aabb::from_boxes(a.bounding_box(), b.bounding_box());
+        Self { a, b, bbox }
+    }
+}
+
+impl Hittable for Union {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        self.hit_all(r, ray_t).into_iter().next()
+    }
+
+    fn hit_all(&self, r: &Ray, ray_t: &Interval) -> Vec<HitRecord> {
+        combined_hits(&*self.a, &*self.b, r, ray_t, |in_a, in_b| in_a || in_b)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+impl Display for Union {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "union({}, {})", self.a, self.b)
+    }
+}
+
+/// Only what's inside both of two closed objects.
+pub struct Intersection {
+    a: Arc<dyn Hittable>,
+    b: Arc<dyn Hittable>,
+    bbox: Aabb,
+}
+
+impl Intersection {
+    pub fn new(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        let (box_a, box_b) = (a.bounding_box(), b.bounding_box());
+        let overlap = |a: &Interval, b: &Interval| interval(a.min.max(b.min), a.max.min(b.max));
+        let bbox = Aabb::new(
+            overlap(&box_a.x, &box_b.x),
+            overlap(&box_a.y, &box_b.y),
+            overlap(&box_a.z, &box_b.z),
+        );
+
+        Self { a, b, bbox }
+    }
+}
+
+impl Hittable for Intersection {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        self.hit_all(r, ray_t).into_iter().next()
+    }
+
+    fn hit_all(&self, r: &Ray, ray_t: &Interval) -> Vec<HitRecord> {
+        combined_hits(&*self.a, &*self.b, r, ray_t, |in_a, in_b| in_a && in_b)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+impl Display for Intersection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "intersection({}, {})", self.a, self.b)
+    }
+}
+
+/// The closed object `a` with everything inside `b` cut out of it. The cut
+/// surfaces take the material of `b`.
+pub struct Difference {
+    a: Arc<dyn Hittable>,
+    b: Arc<dyn Hittable>,
+}
+
+impl Difference {
+    pub fn new(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self { a, b }
+    }
+}
+
+impl Hittable for Difference {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        self.hit_all(r, ray_t).into_iter().next()
+    }
+
+    fn hit_all(&self, r: &Ray, ray_t: &Interval) -> Vec<HitRecord> {
+        combined_hits(&*self.a, &*self.b, r, ray_t, |in_a, in_b| in_a && !in_b)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.a.bounding_box()
+    }
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "difference({}, {})", self.a, self.b)
+    }
+}
+
+/// Walks the hits on `a` and `b` in order, keeping the ones where the ray
+/// crosses into or out of the solid described by `inside`.
+fn combined_hits(
+    a: &dyn Hittable,
+    b: &dyn Hittable,
+    r: &Ray,
+    ray_t: &Interval,
+    inside: fn(bool, bool) -> bool,
+) -> Vec<HitRecord> {
+    // hits before `ray_t` are left out, as the ray has already passed them,
+    // but the ones past it are still needed to tell what it's inside of
+    let ahead = interval(ray_t.min, Float::INFINITY);
+    let hits_a = a.hit_all(r, &ahead);
+    let hits_b = b.hit_all(r, &ahead);
+
+    // a ray starts inside a closed object exactly when the first hit on it
+    // is on the way out
+    let mut in_a = hits_a.first().is_some_and(|rec| !rec.front_face);
+    let mut in_b = hits_b.first().is_some_and(|rec| !rec.front_face);
+    let mut was_inside = inside(in_a, in_b);
+
+    let mut crossings: Vec<_> = hits_a
+        .into_iter()
+        .map(|rec| (true, rec))
+        .chain(hits_b.into_iter().map(|rec| (false, rec)))
+        .collect();
+    crossings.sort_by(|(_, a), (_, b)| a.t.total_cmp(&b.t));
+
+    let mut hits = Vec::new();
+    for (on_a, mut rec) in crossings {
+        if rec.t >= ray_t.max {
+            break;
+        }
+
+        if on_a {
+            in_a = rec.front_face;
+        } else {
+            in_b = rec.front_face;
+        }
+
+        let is_inside = inside(in_a, in_b);
+        if is_inside != was_inside {
+            // the normals already face the ray, only which side of the new
+            // surface is outside can change, as it does for the cuts made by
+            // a difference
+            rec.front_face = is_inside;
+            hits.push(rec);
+        }
+        was_inside = is_inside;
+    }
+
+    hits
+}
+
+pub fn union(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Arc<Union> {
+    Arc::new(Union::new(a, b))
+}
+
+pub fn intersection(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Arc<Intersection> {
+    Arc::new(Intersection::new(a, b))
+}
+
+pub fn difference(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Arc<Difference> {
+    Arc::new(Difference::new(a, b))
+}
@@ -0,0 +1,404 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    materials::Material,
+    primitives::{Aabb, Float, Interval, Onb, Point3, Ray, Vec3, consts::SQRT_2, vec3},
+    tern,
+};
+
+use super::{HitRecord, Hittable};
+
+/// How the cross section of a `Curve` is modelled. All of them are flat
+/// ribbons as far as intersection goes, which is only a good approximation
+/// for curves a few pixels wide or less, like hair and fur.
+#[derive(Debug, Clone, Copy)]
+pub enum CurveShape {
+    /// A ribbon that always faces the incoming ray.
+    Flat,
+    /// A ribbon that always faces the incoming ray, with its normal bent
+    /// across its width so it shades like a round tube.
+    Tube,
+    /// A ribbon with a fixed orientation, given by its normals at either
+    /// end, such as a blade of grass.
+    Ribbon { normals: [Vec3; 2] },
+}
+
+/// What the segments of a curve share.
+struct CurveCommon {
+    control_points: [Point3; 4],
+    widths: [Float; 2],
+    shape: CurveShape,
+    normal_angle: Float,
+    inv_sin_normal_angle: Float,
+    mat: Arc<dyn Material>,
+}
+
+/// A cubic Bézier curve whose width varies linearly along it. `u` runs
+/// along the curve and `v` across it. Long curves intersect faster when
+/// broken up with `segments`, so the BVH can tell the pieces apart.
+pub struct Curve {
+    common: Arc<CurveCommon>,
+    control_points: [Point3; 4],
+    u_min: Float,
+    u_max: Float,
+    bbox: Aabb,
+}
+
+/// The closest hit found so far, in the ray space used by `Curve::hit`.
+struct CurveHit {
+    z: Float,
+    u: Float,
+    v: Float,
+    width: Float,
+    ribbon_normal: Vec3,
+}
+
+impl Curve {
+    pub fn new(
+        control_points: [Point3; 4],
+        widths: [Float; 2],
+        shape: CurveShape,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        let (shape, normal_angle, inv_sin_normal_angle) = match shape {
+            CurveShape::Ribbon { normals: [n0, n1] } => {
+                let normals = [n0.unit_vector(), n1.unit_vector()];
+                let angle = normals[0].dot(normals[1]).clamp(-1.0, 1.0).acos();
+                (CurveShape::Ribbon { normals }, angle, 1.0 / angle.sin())
+            }
+            shape => (shape, 0.0, 0.0),
+        };
+
+        let common = CurveCommon {
+            control_points,
+            widths,
+            shape,
+            normal_angle,
+            inv_sin_normal_angle,
+            mat,
+        };
+
+        Self::segment(Arc::new(common), 0.0, 1.0)
+    }
+
+    fn segment(common: Arc<CurveCommon>, u_min: Float, u_max: Float) -> Self {
+        let cp = &common.control_points;
+        let control_points = [
+            blossom(cp, u_min, u_min, u_min),
+            blossom(cp, u_min, u_min, u_max),
+            blossom(cp, u_min, u_max, u_max),
+            blossom(cp, u_max, u_max, u_max),
+        ];
+
+        // the curve lies within the hull of its control points, give or
+        // take half its width
+        let half_width = 0.5 * common.width(u_min).max(common.width(u_max));
+        let bbox = control_points.iter().fold(
+            Aabb::from_points(control_points[0], control_points[0]),
+            |bbox, &p| {
+                let extent = vec3(half_width, half_width, half_width);
+                Aabb::from_boxes(&bbox, &Aabb::from_points(p - extent, p + extent))
+            },
+        );
+
+        Self {
+            common,
+            control_points,
+            u_min,
+            u_max,
+            bbox,
+        }
+    }
+
+    /// Splits the curve into `count` pieces of equal parameter length.
+    pub fn segments(&self, count: usize) -> impl Iterator<Item = Arc<Self>> + '_ {
+        let step = (self.u_max - self.u_min) / count as Float;
+        (0..count).map(move |i| {
+            let u_min = self.u_min + step * i as Float;
+            let u_max = tern!(i + 1 == count, self.u_max, u_min + step);
+            Arc::new(Self::segment(self.common.clone(), u_min, u_max))
+        })
+    }
+
+    /// Searches the part of the curve between `u0` and `u1` for a hit closer
+    /// than `z_max`, splitting it in half `depth` more times first. `cp` is
+    /// in a space where the ray starts at the origin and runs along z.
+    #[allow(clippy::too_many_arguments)]
+    fn intersect(
+        &self,
+        cp: &[Point3; 4],
+        u0: Float,
+        u1: Float,
+        depth: u32,
+        z_min: Float,
+        z_max: &mut Float,
+        dir: Vec3,
+        closest: &mut Option<CurveHit>,
+    ) {
+        let half_width = 0.5 * self.common.width(u0).max(self.common.width(u1));
+        let bounds = |axis: fn(&Point3) -> Float| {
+            let (min, max) = cp
+                .iter()
+                .map(axis)
+                .fold((Float::INFINITY, Float::NEG_INFINITY), |(min, max), x| {
+                    (min.min(x), max.max(x))
+                });
+            (min - half_width, max + half_width)
+        };
+
+        let ((x_min, x_max), (y_min, y_max), (cp_z_min, cp_z_max)) =
+            (bounds(|p| p.x), bounds(|p| p.y), bounds(|p| p.z));
+        if x_min > 0.0 || x_max < 0.0 || y_min > 0.0 || y_max < 0.0 {
+            return;
+        }
+        if cp_z_min > *z_max || cp_z_max < z_min {
+            return;
+        }
+
+        if depth > 0 {
+            let split = subdivide(cp);
+            let u_mid = u0.midpoint(u1);
+            let halves = [
+                ([split[0], split[1], split[2], split[3]], u0, u_mid),
+                ([split[3], split[4], split[5], split[6]], u_mid, u1),
+            ];
+            for (cp, u0, u1) in halves {
+                self.intersect(&cp, u0, u1, depth - 1, z_min, z_max, dir, closest);
+            }
+            return;
+        }
+
+        // the ray has to pass between the lines through either end of the
+        // segment, perpendicular to the curve there
+        let edge = (cp[1].y - cp[0].y) * -cp[0].y + cp[0].x * (cp[0].x - cp[1].x);
+        if edge < 0.0 {
+            return;
+        }
+        let edge = (cp[2].y - cp[3].y) * -cp[3].y + cp[3].x * (cp[3].x - cp[2].x);
+        if edge < 0.0 {
+            return;
+        }
+
+        // approximate the closest point on the curve to the ray by the
+        // closest point on the line between the ends of the segment
+        let (seg_x, seg_y) = (cp[3].x - cp[0].x, cp[3].y - cp[0].y);
+        let denom = seg_x * seg_x + seg_y * seg_y;
+        if denom == 0.0 {
+            return;
+        }
+        let w = -(cp[0].x * seg_x + cp[0].y * seg_y) / denom;
+        let u = (u0 + (u1 - u0) * w).clamp(u0, u1);
+
+        let mut width = self.common.width(u);
+        let mut ribbon_normal = Vec3::default();
+        if let CurveShape::Ribbon { normals: [n0, n1] } = self.common.shape {
+            // slerp between the end normals, then narrow the ribbon as it
+            // turns edge on to the ray
+            let angle = self.common.normal_angle;
+            let inv_sin = self.common.inv_sin_normal_angle;
+            ribbon_normal = if angle > 0.0 {
+                n0 * (((1.0 - u) * angle).sin() * inv_sin) + n1 * ((u * angle).sin() * inv_sin)
+            } else {
+                n0
+            };
+            width *= ribbon_normal.dot(dir).abs();
+        }
+
+        let (pc, dpcdw) = eval_bezier(cp, w.clamp(0.0, 1.0));
+        let dist_squared = pc.x * pc.x + pc.y * pc.y;
+        if dist_squared > width * width * 0.25 {
+            return;
+        }
+        if pc.z < z_min || pc.z > *z_max {
+            return;
+        }
+
+        // which side of the curve the ray passed tells which half of the
+        // width it's in
+        let dist = dist_squared.sqrt();
+        let edge = dpcdw.x * -pc.y + pc.x * dpcdw.y;
+        let v = tern!(edge > 0.0, 0.5 + dist / width, 0.5 - dist / width);
+
+        *z_max = pc.z;
+        *closest = Some(CurveHit {
+            z: pc.z,
+            u,
+            v,
+            width,
+            ribbon_normal,
+        });
+    }
+}
+
+impl CurveCommon {
+    fn width(&self, u: Float) -> Float {
+        self.widths[0] + (self.widths[1] - self.widths[0]) * u
+    }
+}
+
+impl Hittable for Curve {
+    #[allow(clippy::similar_names)]
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        // work in a space with the ray along z, turned so that x is across
+        // the curve, where the tests reduce to 2D
+        let dir_length = r.dir.length();
+        let dir = r.dir / dir_length;
+        let across = dir.cross(self.control_points[3] - self.control_points[0]);
+        let frame = if across.length_squared() == 0.0 {
+            Onb::new(dir)
+        } else {
+            let across = across.unit_vector();
+            Onb::from_basis(across, dir.cross(across), dir)
+        };
+
+        let to_ray_space = |v: Vec3| vec3(v.dot(frame.u()), v.dot(frame.v()), v.dot(frame.w()));
+        let cp = self.control_points.map(|p| to_ray_space(p - r.orig));
+
+        // split until the segments are about as straight as the curve is
+        // wide, after "Ray Tracing for Curves Primitive" (Nakamaru and Ohno)
+        let l0 = (0..2)
+            .map(|i| {
+                let d = cp[i] - cp[i + 1] * 2.0 + cp[i + 2];
+                d.abs().max_component()
+            })
+            .fold(0.0, Float::max);
+        let epsilon = self.common.widths[0].max(self.common.widths[1]) * 0.05;
+        let r0 = (SQRT_2 * 6.0 * l0 / (8.0 * epsilon)).log2().floor() as i32 / 2;
+        let depth = r0.clamp(0, 10) as u32;
+
+        let mut z_max = ray_t.max * dir_length;
+        let mut closest = None;
+        self.intersect(
+            &cp,
+            self.u_min,
+            self.u_max,
+            depth,
+            ray_t.min * dir_length,
+            &mut z_max,
+            dir,
+            &mut closest,
+        );
+        let hit = closest?;
+        if hit.z <= ray_t.min * dir_length {
+            return None;
+        }
+
+        let t = hit.z / dir_length;
+        let p = r.at(t);
+        let (_, dpdu) = eval_bezier(&self.common.control_points, hit.u);
+
+        let dpdv = if let CurveShape::Ribbon { .. } = self.common.shape {
+            hit.ribbon_normal.cross(dpdu).unit_vector() * hit.width
+        } else {
+            // across the curve, facing the ray
+            let dpdu_plane = to_ray_space(dpdu);
+            let dpdv_plane = vec3(-dpdu_plane.y, dpdu_plane.x, 0.0).unit_vector() * hit.width;
+            let dpdv_plane = if let CurveShape::Tube = self.common.shape {
+                // tilt the ribbon around the curve, as far as edge on at
+                // either side, so the normal follows a round cross section
+                let theta = (hit.v * 180.0 - 90.0).to_radians();
+                rotate(dpdv_plane, dpdu_plane.unit_vector(), theta)
+            } else {
+                dpdv_plane
+            };
+            frame.transform(dpdv_plane)
+        };
+
+        let outward_normal = dpdu.cross(dpdv).unit_vector();
+        let p_error = vec3(1.0, 1.0, 1.0) * (2.0 * hit.width);
+        let rec = HitRecord::new(
+            p,
+            self.common.mat.clone(),
+            t,
+            hit.u,
+            hit.v,
+            r,
+            outward_normal,
+        )
+        .with_p_error(p_error)
+        .with_derivatives(dpdu, dpdv);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+impl Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [p0, p1, p2, p3] = self.control_points;
+        write!(
+            f,
+            "curve({p0}, {p1}, {p2}, {p3}, {:?}, {})",
+            self.common.shape, self.common.mat
+        )
+    }
+}
+
+pub fn curve(
+    control_points: [Point3; 4],
+    widths: [Float; 2],
+    shape: CurveShape,
+    mat: Arc<dyn Material>,
+) -> Arc<Curve> {
+    Arc::new(Curve::new(control_points, widths, shape, mat))
+}
+
+fn lerp(t: Float, a: Point3, b: Point3) -> Point3 {
+    a * (1.0 - t) + b * t
+}
+
+/// The point of the blossom of the curve at `(u0, u1, u2)`. Blossoming at
+/// the ends of a range gives the control points of that part of the curve.
+fn blossom(cp: &[Point3; 4], u0: Float, u1: Float, u2: Float) -> Point3 {
+    let a = [
+        lerp(u0, cp[0], cp[1]),
+        lerp(u0, cp[1], cp[2]),
+        lerp(u0, cp[2], cp[3]),
+    ];
+    let b = [lerp(u1, a[0], a[1]), lerp(u1, a[1], a[2])];
+    lerp(u2, b[0], b[1])
+}
+
+/// Splits the curve in half, returning the control points of both halves
+/// with the middle one shared.
+fn subdivide(cp: &[Point3; 4]) -> [Point3; 7] {
+    [
+        cp[0],
+        (cp[0] + cp[1]) / 2.0,
+        (cp[0] + cp[1] * 2.0 + cp[2]) / 4.0,
+        (cp[0] + cp[1] * 3.0 + cp[2] * 3.0 + cp[3]) / 8.0,
+        (cp[1] + cp[2] * 2.0 + cp[3]) / 4.0,
+        (cp[2] + cp[3]) / 2.0,
+        cp[3],
+    ]
+}
+
+/// The point at `u` on the curve and the derivative there.
+fn eval_bezier(cp: &[Point3; 4], u: Float) -> (Point3, Vec3) {
+    let a = [
+        lerp(u, cp[0], cp[1]),
+        lerp(u, cp[1], cp[2]),
+        lerp(u, cp[2], cp[3]),
+    ];
+    let b = [lerp(u, a[0], a[1]), lerp(u, a[1], a[2])];
+
+    // the derivative vanishes where control points coincide, in which case
+    // the line between the ends points the right way
+    let derivative = tern!(
+        (b[1] - b[0]).length_squared() > 0.0,
+        (b[1] - b[0]) * 3.0,
+        cp[3] - cp[0]
+    );
+    (lerp(u, b[0], b[1]), derivative)
+}
+
+/// Rotates `v` by `angle` around the unit vector `axis`.
+fn rotate(v: Vec3, axis: Vec3, angle: Float) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    v * cos + axis.cross(v) * sin + axis * (axis.dot(v) * (1.0 - cos))
+}
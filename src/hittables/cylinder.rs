@@ -0,0 +1,156 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    materials::Material,
+    misc::rand_float,
+    primitives::{
+        Aabb, Float, Interval, Point3, Ray, Vec3, consts::PI, gamma, interval, point3, ray, vec3,
+    },
+};
+
+use super::{
+    HitRecord, Hittable, HittableList, disk,
+    local_frame::{LocalFrame, azimuth, solve_quadratic},
+};
+
+/// The open side of a cylinder, running from `base` to `base + axis`. `u`
+/// runs around the axis and `v` along it.
+pub struct Cylinder {
+    base: Point3,
+    frame: LocalFrame,
+    radius: Float,
+    height: Float,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+    area: Float,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, axis: Vec3, radius: Float, mat: Arc<dyn Material>) -> Self {
+        let height = axis.length();
+        let frame = LocalFrame::new(base, axis);
+        let bbox = frame.bounding_box(
+            point3(-radius, -radius, 0.0),
+            point3(radius, radius, height),
+        );
+
+        Self {
+            base,
+            frame,
+            radius,
+            height,
+            mat,
+            bbox,
+            area: 2.0 * PI * radius * height,
+        }
+    }
+
+    #[allow(clippy::similar_names)]
+    fn hit_record(&self, r: &Ray, t: Float, local_p: Point3) -> HitRecord {
+        // project the hit point back onto the side to undo the error from
+        // stepping along the ray
+        let scale = self.radius / local_p.x.hypot(local_p.y);
+        let (x, y, z) = (local_p.x * scale, local_p.y * scale, local_p.z);
+        let local_p = point3(x, y, z);
+        let (p, p_error) = self.frame.to_world_point(local_p, local_p.abs() * gamma(3));
+
+        let u = azimuth(x, y) / (2.0 * PI);
+        let v = z / self.height;
+        let dpdu = vec3(-y, x, 0.0) * (2.0 * PI);
+        let dpdv = vec3(0.0, 0.0, self.height);
+
+        let outward_normal = self.frame.to_world_vector(vec3(x, y, 0.0) / self.radius);
+        HitRecord::new(p, self.mat.clone(), t, u, v, r, outward_normal)
+            .with_p_error(p_error)
+            .with_derivatives(
+                self.frame.to_world_vector(dpdu),
+                self.frame.to_world_vector(dpdv),
+            )
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let local = self.frame.to_local(r);
+        let (o, d) = (local.orig, local.dir);
+
+        let a = d.x * d.x + d.y * d.y;
+        let h = -(o.x * d.x + o.y * d.y);
+        let c = o.x * o.x + o.y * o.y - self.radius * self.radius;
+
+        // the far root can still hit if the near one is off the ends
+        solve_quadratic(a, h, c)?
+            .into_iter()
+            .filter(|&t| ray_t.surrounds(t))
+            .map(|t| (t, local.at(t)))
+            .find(|(_, p)| (0.0..=self.height).contains(&p.z))
+            .map(|(t, p)| self.hit_record(r, t, p))
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> Float {
+        // a direction can cross the side twice, and either point could have
+        // been sampled
+        let r = ray(origin, dir, 0.0);
+        let mut pdf = 0.0;
+        let mut t_min = 0.0;
+
+        while let Some(rec) = self.hit(&r, &interval(t_min, Float::INFINITY)) {
+            let distance_squared = rec.t * rec.t * dir.length_squared();
+            let cosine = (dir.dot(rec.geometric_normal) / dir.length()).abs();
+
+            pdf += distance_squared / (cosine * self.area);
+            t_min = rec.t;
+        }
+
+        pdf
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        let (sin, cos) = (2.0 * PI * rand_float()).sin_cos();
+        let local_p = point3(
+            self.radius * cos,
+            self.radius * sin,
+            self.height * rand_float(),
+        );
+
+        let (p, _) = self.frame.to_world_point(local_p, Vec3::default());
+        p - origin
+    }
+}
+
+impl Display for Cylinder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cylinder({}, {}, {}, {})",
+            self.base, self.radius, self.height, self.mat
+        )
+    }
+}
+
+pub fn cylinder(base: Point3, axis: Vec3, radius: Float, mat: Arc<dyn Material>) -> Arc<Cylinder> {
+    Arc::new(Cylinder::new(base, axis, radius, mat))
+}
+
+/// A closed cylinder, made of its side and a disk over each end.
+pub fn capped_cylinder(
+    base: Point3,
+    axis: Vec3,
+    radius: Float,
+    mat: Arc<dyn Material>,
+) -> Arc<HittableList> {
+    let mut parts = HittableList::default();
+
+    parts.add(cylinder(base, axis, radius, mat.clone()));
+    parts.add(disk(base, -axis, radius, mat.clone()));
+    parts.add(disk(base + axis, axis, radius, mat));
+
+    Arc::new(parts)
+}
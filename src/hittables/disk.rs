@@ -0,0 +1,167 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    materials::Material,
+    misc::rand_float,
+    primitives::{
+        Aabb, Float, Interval, Point3, Ray, Vec3, consts::PI, gamma, interval, point3, ray, vec3,
+    },
+    tern,
+};
+
+use super::{
+    HitRecord, Hittable,
+    local_frame::{LocalFrame, azimuth},
+};
+
+/// A flat disk facing along `normal`, optionally with a hole in the middle.
+/// `u` runs around the center and `v` runs from the outer edge inwards.
+pub struct Disk {
+    center: Point3,
+    frame: LocalFrame,
+    inner_radius: Float,
+    radius: Float,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+    area: Float,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: Float, mat: Arc<dyn Material>) -> Self {
+        Self::annulus(center, normal, 0.0, radius, mat)
+    }
+
+    /// A disk with the part within `inner_radius` of the center cut out.
+    ///
+    /// # Panics
+    /// If `inner_radius` isn't less than `radius`, leaving nothing.
+    pub fn annulus(
+        center: Point3,
+        normal: Vec3,
+        inner_radius: Float,
+        radius: Float,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        assert!(inner_radius < radius, "annulus has no area");
+        let frame = LocalFrame::new(center, normal);
+        let bbox = frame.bounding_box(point3(-radius, -radius, 0.0), point3(radius, radius, 0.0));
+
+        Self {
+            center,
+            frame,
+            inner_radius,
+            radius,
+            mat,
+            bbox,
+            area: PI * (radius * radius - inner_radius * inner_radius),
+        }
+    }
+
+    #[allow(clippy::similar_names)]
+    fn hit_record(&self, r: &Ray, t: Float, x: Float, y: Float) -> HitRecord {
+        let dist = x.hypot(y);
+        let u = azimuth(x, y) / (2.0 * PI);
+        let v = (self.radius - dist) / (self.radius - self.inner_radius);
+
+        // the hit point is exactly on the plane in local space, so only the
+        // move back to world space adds error along the normal
+        let local_p = point3(x, y, 0.0);
+        let (p, p_error) = self
+            .frame
+            .to_world_point(local_p, vec3(x.abs(), y.abs(), 0.0) * gamma(5));
+
+        let dpdu = vec3(-y, x, 0.0) * (2.0 * PI);
+        let dpdv = tern!(
+            dist > 0.0,
+            vec3(x, y, 0.0) * ((self.inner_radius - self.radius) / dist),
+            Vec3::default()
+        );
+
+        let outward_normal = self.frame.to_world_vector(vec3(0.0, 0.0, 1.0));
+        HitRecord::new(p, self.mat.clone(), t, u, v, r, outward_normal)
+            .with_p_error(p_error)
+            .with_derivatives(
+                self.frame.to_world_vector(dpdu),
+                self.frame.to_world_vector(dpdv),
+            )
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let local = self.frame.to_local(r);
+        if local.dir.z == 0.0 {
+            return None;
+        }
+
+        let t = -local.orig.z / local.dir.z;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        let p = local.at(t);
+        let dist_squared = p.x * p.x + p.y * p.y;
+        if dist_squared > self.radius * self.radius
+            || dist_squared < self.inner_radius * self.inner_radius
+        {
+            return None;
+        }
+
+        Some(self.hit_record(r, t, p.x, p.y))
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> Float {
+        if let Some(rec) = self.hit(&ray(origin, dir, 0.0), &interval(0.0, Float::INFINITY)) {
+            let distance_squared = rec.t * rec.t * dir.length_squared();
+            let cosine = (dir.dot(rec.geometric_normal) / dir.length()).abs();
+
+            distance_squared / (cosine * self.area)
+        } else {
+            0.0
+        }
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        // uniform in area, so the radius is weighted towards the outer edge
+        let inner_squared = self.inner_radius * self.inner_radius;
+        let radius_squared = self.radius * self.radius;
+        let dist = (inner_squared + rand_float() * (radius_squared - inner_squared)).sqrt();
+        let (sin, cos) = (2.0 * PI * rand_float()).sin_cos();
+
+        let (p, _) = self
+            .frame
+            .to_world_point(point3(dist * cos, dist * sin, 0.0), Vec3::default());
+        p - origin
+    }
+}
+
+impl Display for Disk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "disk({}, {}, {}, {})",
+            self.center, self.inner_radius, self.radius, self.mat
+        )
+    }
+}
+
+pub fn disk(center: Point3, normal: Vec3, radius: Float, mat: Arc<dyn Material>) -> Arc<Disk> {
+    Arc::new(Disk::new(center, normal, radius, mat))
+}
+
+pub fn annulus(
+    center: Point3,
+    normal: Vec3,
+    inner_radius: Float,
+    radius: Float,
+    mat: Arc<dyn Material>,
+) -> Arc<Disk> {
+    Arc::new(Disk::annulus(center, normal, inner_radius, radius, mat))
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use crate::{
+    primitives::{Float, Point3, Vec3},
+    tern,
+    textures::Texture,
+};
+
+/// The result of `displace`, ready to become a `TriangleMesh`.
+pub(super) struct DisplacedMesh {
+    pub positions: Vec<Point3>,
+    pub indices: Vec<[u32; 3]>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Option<Vec<(Float, Float)>>,
+}
+
+/// The vertices and triangles of a mesh being tessellated.
+struct Tessellation {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Option<Vec<(Float, Float)>>,
+    indices: Vec<[u32; 3]>,
+}
+
+impl Tessellation {
+    /// Splits every triangle in four through the midpoints of its edges,
+    /// which are shared with the triangle across each edge.
+    fn split(self) -> Self {
+        let Self {
+            mut positions,
+            mut normals,
+            mut uvs,
+            indices,
+        } = self;
+
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (a, b) = (a as usize, b as usize);
+                positions.push((positions[a] + positions[b]) * 0.5);
+                normals.push((normals[a] + normals[b]).unit_vector());
+                if let Some(uvs) = &mut uvs {
+                    let ((u0, v0), (u1, v1)) = (uvs[a], uvs[b]);
+                    uvs.push((u0.midpoint(u1), v0.midpoint(v1)));
+                }
+                positions.len() as u32 - 1
+            })
+        };
+
+        let indices = indices
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect();
+
+        Self {
+            positions,
+            normals,
+            uvs,
+            indices,
+        }
+    }
+}
+
+/// Area weighted normals of the faces around each group of vertices.
+fn smooth_normals(
+    positions: &[Point3],
+    indices: &[[u32; 3]],
+    groups: &[usize],
+    group_count: usize,
+) -> Vec<Vec3> {
+    let mut sums = vec![Vec3::default(); group_count];
+    for &[a, b, c] in indices {
+        let [pa, pb, pc] = [a, b, c].map(|i| positions[i as usize]);
+        let normal = (pb - pa).cross(pc - pa);
+        for i in [a, b, c] {
+            sums[groups[i as usize]] += normal;
+        }
+    }
+
+    sums.into_iter()
+        .map(|n| tern!(n.length_squared() > 0.0, n.unit_vector(), n))
+        .collect()
+}
+
+/// Numbers the distinct keys of `items`, returning the number each item got
+/// and how many there are.
+fn group_by<K: std::hash::Hash + Eq>(items: impl Iterator<Item = K>) -> (Vec<usize>, usize) {
+    let mut ids = HashMap::new();
+    let groups = items
+        .map(|key| {
+            let next = ids.len();
+            *ids.entry(key).or_insert(next)
+        })
+        .collect();
+    (groups, ids.len())
+}
+
+fn position_key(p: Point3) -> [impl std::hash::Hash + Eq; 3] {
+    [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]
+}
+
+/// Tessellates a mesh with `levels` rounds of splitting every triangle in
+/// four, then moves each vertex along its normal by the brightness of
+/// `height` times `scale`. Vertices at the same position move together, so
+/// the mesh stays closed across seams where it has split vertices.
+pub(super) fn displace(
+    positions: &[Point3],
+    indices: &[[u32; 3]],
+    normals: Option<&[Vec3]>,
+    uvs: Option<&[(Float, Float)]>,
+    height: &dyn Texture,
+    scale: Float,
+    levels: u32,
+) -> DisplacedMesh {
+    let normals = normals.map_or_else(
+        || {
+            smooth_normals(
+                positions,
+                indices,
+                &(0..positions.len()).collect::<Vec<_>>(),
+                positions.len(),
+            )
+        },
+        <[Vec3]>::to_vec,
+    );
+
+    let mut mesh = Tessellation {
+        positions: positions.to_vec(),
+        normals,
+        uvs: uvs.map(<[_]>::to_vec),
+        indices: indices.to_vec(),
+    };
+    for _ in 0..levels {
+        mesh = mesh.split();
+    }
+
+    // average the direction and distance of every vertex at a position
+    let (welded, welded_count) = group_by(mesh.positions.iter().map(|&p| position_key(p)));
+    let mut offsets = vec![(Vec3::default(), 0.0, 0_usize); welded_count];
+    for (i, &p) in mesh.positions.iter().enumerate() {
+        let (u, v) = mesh.uvs.as_ref().map_or((0.0, 0.0), |uvs| uvs[i]);
+        let (dir, h, count) = &mut offsets[welded[i]];
+        *dir += mesh.normals[i];
+        *h += height.value(u, v, p).average() * scale;
+        *count += 1;
+    }
+
+    let displaced: Vec<Point3> = mesh
+        .positions
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| {
+            let (dir, h, count) = offsets[welded[i]];
+            tern!(
+                dir.length_squared() > 0.0,
+                p + dir.unit_vector() * (h / count as Float),
+                p
+            )
+        })
+        .collect();
+
+    // vertices that were only split for their texture coordinates are
+    // smoothed together, while those with different normals keep their
+    // hard edge
+    let (shading, shading_count) = group_by(
+        mesh.positions
+            .iter()
+            .zip(&mesh.normals)
+            .map(|(&p, n)| (position_key(p), position_key(*n))),
+    );
+    let group_normals = smooth_normals(&displaced, &mesh.indices, &shading, shading_count);
+
+    DisplacedMesh {
+        positions: displaced,
+        indices: mesh.indices,
+        normals: shading.iter().map(|&g| group_normals[g]).collect(),
+        uvs: mesh.uvs,
+    }
+}
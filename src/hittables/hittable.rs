@@ -2,38 +2,64 @@ use std::{fmt, sync::Arc};
 
 use crate::{
     materials::Material,
-    primitives::{Aabb, Interval, Point3, Ray, Vec3, vec3},
+    primitives::{
+        Aabb, Float, Interval, Onb, PACKET_WIDTH, PacketMask, Point3, Ray, RayPacket, Vec3,
+        interval, ray, vec3,
+    },
     tern,
 };
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
+    /// A conservative bound on the absolute rounding error in each component
+    /// of `p`.
+    pub p_error: Vec3,
+    /// The normal used for shading, which may be interpolated or perturbed.
+    /// Always on the same side of the surface as `geometric_normal`.
     pub normal: Vec3,
+    /// The true normal of the surface, facing against the incoming ray.
+    pub geometric_normal: Vec3,
+    /// Unit vectors completing an orthonormal frame with `normal`, following
+    /// `dpdu` and `dpdv` where the surface has them.
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    /// How `p` changes with `u` and `v`, which orients textures on the
+    /// surface. Zero for surfaces without a parameterization.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
     pub mat: Arc<dyn Material>,
-    pub t: f64,
-    pub u: f64,
-    pub v: f64,
+    pub t: Float,
+    pub u: Float,
+    pub v: Float,
     pub front_face: bool,
 }
 
 impl HitRecord {
-    /// `outward_normal` is assumed to have unit length
+    /// `outward_normal` is assumed to have unit length. `p` is assumed to be
+    /// exact until an error bound is given with `with_p_error`.
     pub fn new(
         p: Point3,
         mat: Arc<dyn Material>,
-        t: f64,
-        u: f64,
-        v: f64,
+        t: Float,
+        u: Float,
+        v: Float,
         r: &Ray,
         outward_normal: Vec3,
     ) -> Self {
         let front_face = r.dir.dot(outward_normal) < 0.0;
         let normal = tern!(front_face, outward_normal, -outward_normal);
+        let frame = Onb::new(normal);
 
         Self {
             p,
+            p_error: Vec3::default(),
             normal,
+            geometric_normal: normal,
+            tangent: frame.u(),
+            bitangent: frame.v(),
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
             mat,
             t,
             u,
@@ -41,14 +67,168 @@ impl HitRecord {
             front_face,
         }
     }
+
+    /// Replaces the normal used for shading, for example with one
+    /// interpolated from vertex normals. It's flipped onto the same side as
+    /// the geometric normal, so `front_face` still describes the real
+    /// surface.
+    #[must_use]
+    pub fn with_shading_normal(mut self, normal: Vec3) -> Self {
+        self.normal = tern!(normal.dot(self.geometric_normal) < 0.0, -normal, normal);
+        self.update_tangents();
+        self
+    }
+
+    #[must_use]
+    #[allow(clippy::similar_names)]
+    pub fn with_derivatives(mut self, dpdu: Vec3, dpdv: Vec3) -> Self {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+        self.update_tangents();
+        self
+    }
+
+    /// The shading frame, with `w` along the shading normal and `u` along
+    /// the tangent.
+    #[must_use]
+    pub fn shading_frame(&self) -> Onb {
+        Onb::from_basis(self.tangent, self.bitangent, self.normal)
+    }
+
+    fn update_tangents(&mut self) {
+        let tangent = self.dpdu - self.normal * self.normal.dot(self.dpdu);
+        self.tangent = if tangent.is_near_zero() {
+            Onb::new(self.normal).u()
+        } else {
+            tangent.unit_vector()
+        };
+
+        let bitangent = self.normal.cross(self.tangent);
+        self.bitangent = tern!(bitangent.dot(self.dpdv) < 0.0, -bitangent, bitangent);
+    }
+
+    #[must_use]
+    pub fn with_p_error(mut self, p_error: Vec3) -> Self {
+        self.p_error = p_error;
+        self
+    }
+
+    /// Spawns a ray leaving the hit point in `dir`. The origin is pushed off
+    /// the surface along the geometric normal by just more than `p_error`, so
+    /// the ray can't re-intersect the surface it starts on at any scene
+    /// scale.
+    #[must_use]
+    pub fn spawn_ray(&self, dir: Vec3, time: Float) -> Ray {
+        ray(self.offset_origin(dir), dir, time)
+    }
+
+    /// Whether `dir` leaves the surface on the same side the hit came from.
+    /// Shading normals can disagree with the real surface about this, which
+    /// would otherwise let light leak through it.
+    #[must_use]
+    pub fn is_reflection(&self, dir: Vec3) -> bool {
+        dir.dot(self.geometric_normal) > 0.0
+    }
+
+    fn offset_origin(&self, dir: Vec3) -> Point3 {
+        let n = self.geometric_normal;
+        let d = n.abs().dot(self.p_error);
+        let offset = n * tern!(dir.dot(n) < 0.0, -d, d);
+        let po = self.p + offset;
+
+        // round away from p so the offset can't be lost to rounding
+        let round = |po: Float, offset: Float| match offset {
+            o if o > 0.0 => po.next_up(),
+            o if o < 0.0 => po.next_down(),
+            _ => po,
+        };
+
+        vec3(
+            round(po.x, offset.x),
+            round(po.y, offset.y),
+            round(po.z, offset.z),
+        )
+    }
+}
+
+/// The closest hits found so far for each lane of a `RayPacket`.
+pub struct PacketHits {
+    pub t_max: [Float; PACKET_WIDTH],
+    pub recs: [Option<HitRecord>; PACKET_WIDTH],
+}
+
+impl PacketHits {
+    #[must_use]
+    pub fn new(t_max: Float) -> Self {
+        Self {
+            t_max: [t_max; PACKET_WIDTH],
+            recs: Default::default(),
+        }
+    }
+
+    /// Starts an empty set of hits limited to the same ranges as `self`, for
+    /// when a wrapper needs to post-process the hits of its inner object.
+    #[must_use]
+    pub fn narrowed(&self) -> Self {
+        Self {
+            t_max: self.t_max,
+            recs: Default::default(),
+        }
+    }
+
+    /// Stores `rec` as the closest hit of `lane`, shrinking that lane's range.
+    pub fn record(&mut self, lane: usize, rec: HitRecord) {
+        self.t_max[lane] = rec.t;
+        self.recs[lane] = Some(rec);
+    }
 }
 
 pub trait Hittable: Sync + Send + fmt::Display {
     fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord>;
 
+    /// Intersects every `active` lane of a packet, recording a hit wherever
+    /// one is found closer than that lane's current `hits.t_max`. Defaults to
+    /// calling `hit` once per lane.
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        for lane in (0..PACKET_WIDTH).filter(|&lane| active[lane]) {
+            if let Some(rec) = self.hit(&rays.ray(lane), &interval(t_min, hits.t_max[lane])) {
+                hits.record(lane, rec);
+            }
+        }
+    }
+
+    /// Every intersection with the surface within `ray_t`, nearest first.
+    /// Solid objects like `Union` need these to tell when a ray is inside
+    /// them, which works for closed surfaces, where `front_face` marks each
+    /// hit as an entry or an exit. Defaults to calling `hit` again just past
+    /// each hit.
+    fn hit_all(&self, r: &Ray, ray_t: &Interval) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        let mut t_min = ray_t.min;
+
+        while let Some(rec) = self.hit(r, &interval(t_min, ray_t.max)) {
+            // shapes that report hits on the edge of the range would
+            // otherwise find the same one forever
+            if rec.t <= t_min {
+                break;
+            }
+
+            t_min = rec.t;
+            hits.push(rec);
+        }
+
+        hits
+    }
+
     fn bounding_box(&self) -> &Aabb;
 
-    fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> Float {
         _ = (origin, dir);
         0.0
     }
@@ -1,8 +1,8 @@
 use std::{fmt, sync::Arc};
 
-use crate::primitives::{Aabb, Interval, Ray, interval};
+use crate::primitives::{Aabb, Float, Interval, PacketMask, Ray, RayPacket, interval};
 
-use super::{HitRecord, Hittable};
+use super::{HitRecord, Hittable, PacketHits};
 
 #[derive(Clone, Default)]
 pub struct HittableList {
@@ -25,6 +25,18 @@ impl Hittable for HittableList {
         })
     }
 
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        for object in &self.objects {
+            object.hit_packet(rays, t_min, active, hits);
+        }
+    }
+
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
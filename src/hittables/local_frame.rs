@@ -0,0 +1,84 @@
+use crate::{
+    primitives::{Aabb, Float, Onb, Point3, Ray, Vec3, consts::PI, gamma, point3, ray},
+    tern,
+};
+
+/// The coordinate system of a shape built around an axis, with the origin
+/// at `origin` and z pointing along the axis. Shapes intersect rays in this
+/// space, where their equations are simplest.
+pub(super) struct LocalFrame {
+    origin: Point3,
+    onb: Onb,
+}
+
+impl LocalFrame {
+    pub fn new(origin: Point3, axis: Vec3) -> Self {
+        Self {
+            origin,
+            onb: Onb::new(axis),
+        }
+    }
+
+    pub fn to_local(&self, r: &Ray) -> Ray {
+        ray(
+            self.onb.to_local(r.orig - self.origin),
+            self.onb.to_local(r.dir),
+            r.time,
+        )
+    }
+
+    pub fn to_world_vector(&self, v: Vec3) -> Vec3 {
+        self.onb.transform(v)
+    }
+
+    /// Moves a local point and its error bound into world space.
+    pub fn to_world_point(&self, p: Point3, p_error: Vec3) -> (Point3, Vec3) {
+        let (u, v, w) = (self.onb.u(), self.onb.v(), self.onb.w());
+        let world = self.origin + self.onb.transform(p);
+
+        let abs_transform = |e: Vec3| u.abs() * e.x + v.abs() * e.y + w.abs() * e.z;
+        let error = abs_transform(p_error) * (1.0 + gamma(4))
+            + (self.origin.abs() + abs_transform(p.abs())) * gamma(4);
+
+        (world, error)
+    }
+
+    /// Bounds a local box given by its opposite corners.
+    pub fn bounding_box(&self, min: Point3, max: Point3) -> Aabb {
+        let mut bbox = Aabb::from_points(self.origin, self.origin);
+        for x in [min.x, max.x] {
+            for y in [min.y, max.y] {
+                for z in [min.z, max.z] {
+                    let corner = self.origin + self.onb.transform(point3(x, y, z));
+                    bbox = Aabb::from_boxes(&bbox, &Aabb::from_points(corner, corner));
+                }
+            }
+        }
+        bbox
+    }
+}
+
+/// Solves `a t^2 - 2 h t + c = 0`, returning both roots in ascending order.
+pub(super) fn solve_quadratic(a: Float, h: Float, c: Float) -> Option<[Float; 2]> {
+    if a == 0.0 {
+        // only one root when the equation is linear
+        return (h != 0.0).then(|| [c / (2.0 * h); 2]);
+    }
+
+    let discriminant = h * h - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let discriminant_sqrt = discriminant.sqrt();
+    let t0 = (h - discriminant_sqrt) / a;
+    let t1 = (h + discriminant_sqrt) / a;
+
+    Some(if t0 <= t1 { [t0, t1] } else { [t1, t0] })
+}
+
+/// The angle of `(x, y)` around the z axis, in `[0, 2 pi)`.
+pub(super) fn azimuth(x: Float, y: Float) -> Float {
+    let phi = y.atan2(x);
+    tern!(phi < 0.0, phi + 2.0 * PI, phi)
+}
@@ -1,21 +1,40 @@
 mod bvh_node;
+mod cone;
 mod constant_medium;
+mod csg;
+mod curve;
+mod cylinder;
+mod disk;
+mod displacement;
 mod hittable;
 mod hittable_list;
+mod local_frame;
 mod moving;
+mod plane;
 mod quad;
 mod rotate_y;
+mod sdf;
 mod sphere;
+mod subdivision;
 mod translate;
 mod triangle;
+mod triangle_mesh;
 
 pub use bvh_node::*;
+pub use cone::*;
 pub use constant_medium::*;
+pub use csg::*;
+pub use curve::*;
+pub use cylinder::*;
+pub use disk::*;
 pub use hittable::*;
 pub use hittable_list::*;
 pub use moving::*;
+pub use plane::*;
 pub use quad::*;
 pub use rotate_y::*;
+pub use sdf::*;
 pub use sphere::*;
 pub use translate::*;
 pub use triangle::*;
+pub use triangle_mesh::*;
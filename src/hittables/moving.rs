@@ -3,7 +3,7 @@ use std::{
     sync::Arc,
 };
 
-use crate::primitives::{Aabb, Interval, Ray, Vec3};
+use crate::primitives::{Aabb, Interval, Ray, Vec3, gamma};
 
 use super::{HitRecord, Hittable};
 
@@ -33,6 +33,7 @@ impl Hittable for Moving {
         // check for a hit and move the hit position forward if there was a hit
         let mut rec = self.object.hit(&offset_ray, ray_t)?;
         rec.p += offset;
+        rec.p_error = rec.p_error * (1.0 + gamma(1)) + rec.p.abs() * gamma(1);
 
         Some(rec)
     }
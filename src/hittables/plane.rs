@@ -0,0 +1,92 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    materials::Material,
+    primitives::{Aabb, Float, Interval, Onb, Point3, Ray, Vec3, gamma, interval},
+};
+
+use super::{HitRecord, Hittable};
+
+/// An infinite plane through `point`, facing along `normal`. Texture
+/// coordinates are distances across the plane from `point`, so repeating
+/// textures tile it once per unit.
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    frame: Onb,
+    d: Float,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, mat: Arc<dyn Material>) -> Self {
+        let normal = normal.unit_vector();
+
+        // the plane only has an extent along an axis it's perpendicular to
+        let extent = |axis: u8| {
+            let is_perpendicular = (0..3)
+                .filter(|&other| other != axis)
+                .all(|other| normal.axis(other) == 0.0);
+
+            if is_perpendicular {
+                interval(point.axis(axis), point.axis(axis))
+            } else {
+                Interval::full()
+            }
+        };
+
+        Self {
+            point,
+            normal,
+            frame: Onb::new(normal),
+            d: normal.dot(point),
+            mat,
+            bbox: Aabb::new(extent(0), extent(1), extent(2)),
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(r.dir);
+        if denom == 0.0 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(r.orig)) / denom;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        // rebuild the hit point from its coordinates across the plane, which
+        // keeps it much closer to the plane than stepping along the ray
+        let offset = r.at(t) - self.point;
+        let (u, v) = (offset.dot(self.frame.u()), offset.dot(self.frame.v()));
+        let (along_u, along_v) = (self.frame.u() * u, self.frame.v() * v);
+        let p = self.point + along_u + along_v;
+        let p_error = (self.point.abs() + along_u.abs() + along_v.abs()) * gamma(6);
+
+        let rec = HitRecord::new(p, self.mat.clone(), t, u, v, r, self.normal)
+            .with_p_error(p_error)
+            .with_derivatives(self.frame.u(), self.frame.v());
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+impl Display for Plane {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "plane({}, {}, {})", self.point, self.normal, self.mat)
+    }
+}
+
+pub fn plane(point: Point3, normal: Vec3, mat: Arc<dyn Material>) -> Arc<Plane> {
+    Arc::new(Plane::new(point, normal, mat))
+}
@@ -1,34 +1,101 @@
 use std::{
-    f64::INFINITY,
+    array,
     fmt::{self, Display},
     sync::Arc,
 };
 
 use crate::{
     materials::Material,
-    misc::rand_f64,
-    primitives::{Aabb, Interval, Point3, Ray, Vec3, interval, point3, ray, vec3},
+    misc::rand_float,
+    primitives::{
+        Aabb, Float, Interval, Lanes, PACKET_WIDTH, PacketMask, Point3, Ray, RayPacket, Vec3,
+        Vec3Packet, consts::PI, gamma, interval, point3, ray, vec3,
+    },
 };
 
-use super::{HitRecord, Hittable, HittableList};
+use super::{HitRecord, Hittable, HittableList, PacketHits};
+
+/// The part of a quad's plane that is solid, in terms of the coordinates
+/// `alpha` and `beta` of a point `q + alpha * u + beta * v`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuadShape {
+    /// The parallelogram with corners `q`, `q + u`, `q + v` and `q + u + v`.
+    Parallelogram,
+    /// The triangle with corners `q`, `q + u` and `q + v`.
+    Triangle,
+    /// The ellipse centered on `q` with `u` and `v` as its semi-axes.
+    Ellipse,
+    /// An ellipse with a hole in the middle, whose size is given as a
+    /// fraction of the outer ellipse's.
+    Annulus { inner: Float },
+}
+
+impl QuadShape {
+    fn contains(self, alpha: Float, beta: Float) -> bool {
+        let unit_interval = Interval::new(0.0, 1.0);
+        let radius_squared = alpha * alpha + beta * beta;
+
+        match self {
+            Self::Parallelogram => unit_interval.contains(alpha) && unit_interval.contains(beta),
+            Self::Triangle => alpha >= 0.0 && beta >= 0.0 && alpha + beta <= 1.0,
+            Self::Ellipse => radius_squared <= 1.0,
+            Self::Annulus { inner } => (inner * inner..=1.0).contains(&radius_squared),
+        }
+    }
+
+    /// The area of the shape as a fraction of the parallelogram's.
+    fn area_fraction(self) -> Float {
+        match self {
+            Self::Parallelogram => 1.0,
+            Self::Triangle => 0.5,
+            Self::Ellipse => PI,
+            Self::Annulus { inner } => PI * (1.0 - inner * inner),
+        }
+    }
+
+    /// Picks a point uniformly over the shape's area.
+    fn sample(self) -> (Float, Float) {
+        match self {
+            Self::Parallelogram => (rand_float(), rand_float()),
+            Self::Triangle => {
+                let s = rand_float().sqrt();
+                (1.0 - s, rand_float() * s)
+            }
+            Self::Ellipse => Self::Annulus { inner: 0.0 }.sample(),
+            Self::Annulus { inner } => {
+                let inner_squared = inner * inner;
+                let radius = (inner_squared + rand_float() * (1.0 - inner_squared)).sqrt();
+                let (sin, cos) = (2.0 * PI * rand_float()).sin_cos();
+                (radius * cos, radius * sin)
+            }
+        }
+    }
+
+    /// Maps `alpha` and `beta` to texture coordinates in `[0, 1]`, along with
+    /// how much they're scaled by.
+    fn uv(self, alpha: Float, beta: Float) -> (Float, Float, Float) {
+        match self {
+            Self::Parallelogram | Self::Triangle => (alpha, beta, 1.0),
+            Self::Ellipse | Self::Annulus { .. } => (alpha.midpoint(1.0), beta.midpoint(1.0), 0.5),
+        }
+    }
+}
 
 pub struct Quad {
     q: Point3,
     u: Vec3,
     v: Vec3,
     w: Vec3,
+    shape: QuadShape,
     mat: Arc<dyn Material>,
     bbox: Aabb,
     normal: Vec3,
-    d: f64,
-    area: f64,
+    d: Float,
+    area: Float,
 }
 
 impl Quad {
     pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Self {
-        let bbox_diag_1 = Aabb::from_points(q, q + u + v);
-        let bbox_diag_2 = Aabb::from_points(q + u, q + v);
-
         let n = u.cross(v);
         let normal = n.unit_vector();
         let d = normal.dot(q);
@@ -39,24 +106,59 @@ impl Quad {
             u,
             v,
             w,
+            shape: QuadShape::Parallelogram,
             mat,
-            bbox: Aabb::from_boxes(&bbox_diag_1, &bbox_diag_2),
+            bbox: Self::shape_bbox(q, u, v, QuadShape::Parallelogram),
             normal,
             d,
             area: n.length(),
         }
     }
 
-    fn is_within_surface(alpha: f64, beta: f64) -> bool {
-        // parallelogram
-        let unit_interval = Interval::new(0.0, 1.0);
-        unit_interval.contains(alpha) && unit_interval.contains(beta)
+    /// Cuts the quad down to a different shape within its plane. Light
+    /// sampling follows the new shape.
+    ///
+    /// # Panics
+    /// If the hole of an annulus isn't in `[0, 1)`.
+    #[must_use]
+    pub fn with_shape(mut self, shape: QuadShape) -> Self {
+        if let QuadShape::Annulus { inner } = shape {
+            assert!((0.0..1.0).contains(&inner), "annulus hole out of range");
+        }
+        self.area = self.u.cross(self.v).length() * shape.area_fraction();
+        self.bbox = Self::shape_bbox(self.q, self.u, self.v, shape);
+        self.shape = shape;
+        self
+    }
+
+    fn shape_bbox(q: Point3, u: Vec3, v: Vec3, shape: QuadShape) -> Aabb {
+        match shape {
+            QuadShape::Parallelogram => Aabb::from_boxes(
+                &Aabb::from_points(q, q + u + v),
+                &Aabb::from_points(q + u, q + v),
+            ),
+            QuadShape::Triangle => {
+                Aabb::from_boxes(&Aabb::from_points(q, q + u), &Aabb::from_points(q, q + v))
+            }
+            QuadShape::Ellipse | QuadShape::Annulus { .. } => Aabb::from_boxes(
+                &Aabb::from_points(q - u - v, q + u + v),
+                &Aabb::from_points(q + u - v, q - u + v),
+            ),
+        }
+    }
 
-        // disk
-        // (alpha * alpha + beta * beta) < 1.0
+    fn hit_record(&self, r: &Ray, t: Float, alpha: Float, beta: Float) -> HitRecord {
+        // rebuild the hit point from the quad's own parameterization, which
+        // keeps it much closer to the plane than stepping along the ray
+        let (u, v) = (self.u * alpha, self.v * beta);
+        let p = self.q + u + v;
+        let p_error = (self.q.abs() + u.abs() + v.abs()) * gamma(6);
 
-        // triangle
-        // alpha > 0.0 && beta > 0.0 && alpha + beta < 1.0
+        let (tex_u, tex_v, scale) = self.shape.uv(alpha, beta);
+
+        HitRecord::new(p, self.mat.clone(), t, tex_u, tex_v, r, self.normal)
+            .with_p_error(p_error)
+            .with_derivatives(self.u / scale, self.v / scale)
     }
 }
 
@@ -69,7 +171,7 @@ impl Hittable for Quad {
         }
 
         let t = (self.d - self.normal.dot(r.orig)) / denom;
-        if !ray_t.contains(t) {
+        if !ray_t.surrounds(t) {
             return None;
         }
 
@@ -80,29 +182,51 @@ impl Hittable for Quad {
         let alpha = self.w.dot(planar_hitpoint.cross(self.v));
         let beta = self.w.dot(self.u.cross(planar_hitpoint));
 
-        if !Self::is_within_surface(alpha, beta) {
+        if !self.shape.contains(alpha, beta) {
             return None;
         }
 
-        Some(HitRecord::new(
-            intersection,
-            self.mat.clone(),
-            t,
-            alpha,
-            beta,
-            r,
-            self.normal,
-        ))
+        Some(self.hit_record(r, t, alpha, beta))
+    }
+
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        let denom = rays.dir.dot_splat(self.normal);
+        let orig_dot = rays.orig.dot_splat(self.normal);
+        let t: Lanes = array::from_fn(|i| (self.d - orig_dot[i]) / denom[i]);
+
+        let planar_hitpoint = rays.at(&t) - Vec3Packet::splat(self.q);
+        let alpha = planar_hitpoint.cross_splat(self.v).dot_splat(self.w);
+        let beta = planar_hitpoint.cross_splat(self.u).dot_splat(-self.w);
+
+        for lane in (0..PACKET_WIDTH).filter(|&lane| active[lane]) {
+            if denom[lane].abs() < 1e-8
+                || !interval(t_min, hits.t_max[lane]).surrounds(t[lane])
+                || !self.shape.contains(alpha[lane], beta[lane])
+            {
+                continue;
+            }
+
+            hits.record(
+                lane,
+                self.hit_record(&rays.ray(lane), t[lane], alpha[lane], beta[lane]),
+            );
+        }
     }
 
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
 
-    fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
-        if let Some(rec) = self.hit(&ray(origin, dir, 0.0), &interval(0.001, f64::INFINITY)) {
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> Float {
+        if let Some(rec) = self.hit(&ray(origin, dir, 0.0), &interval(0.0, Float::INFINITY)) {
             let distance_squared = rec.t * rec.t * dir.length_squared();
-            let cosine = (dir.dot(rec.normal) / dir.length()).abs();
+            let cosine = (dir.dot(rec.geometric_normal) / dir.length()).abs();
 
             distance_squared / (cosine * self.area)
         } else {
@@ -111,14 +235,19 @@ impl Hittable for Quad {
     }
 
     fn random(&self, origin: Point3) -> Vec3 {
-        let p = self.q + (rand_f64() * self.u) + (rand_f64() * self.v);
+        let (alpha, beta) = self.shape.sample();
+        let p = self.q + (alpha * self.u) + (beta * self.v);
         p - origin
     }
 }
 
 impl Display for Quad {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "quad({}, {}, {}, {})", self.q, self.u, self.v, self.mat)
+        write!(
+            f,
+            "quad({}, {}, {}, {:?}, {})",
+            self.q, self.u, self.v, self.shape, self.mat
+        )
     }
 }
 
@@ -126,6 +255,16 @@ pub fn quad(q: Point3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Arc<Quad> {
     Arc::new(Quad::new(q, u, v, mat))
 }
 
+pub fn shaped_quad(
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    shape: QuadShape,
+    mat: Arc<dyn Material>,
+) -> Arc<Quad> {
+    Arc::new(Quad::new(q, u, v, mat).with_shape(shape))
+}
+
 pub fn cube(a: Point3, b: Point3, material: Arc<dyn Material>) -> Arc<HittableList> {
     let mut sides = HittableList::default();
 
@@ -1,32 +1,38 @@
-use core::f64;
 use std::{
     fmt::{self, Display},
     sync::Arc,
 };
 
-use crate::primitives::{Aabb, Interval, Ray, point3, ray, vec3};
+use crate::primitives::{
+    Aabb, Float, Interval, PacketMask, Point3, Ray, RayPacket, Vec3, Vec3Packet, gamma, point3,
+    ray, vec3,
+};
 
-use super::{HitRecord, Hittable};
+use super::{HitRecord, Hittable, PacketHits};
 
 pub struct RotateY {
     object: Arc<dyn Hittable>,
-    angle: f64,
-    sin_theta: f64,
-    cos_theta: f64,
+    angle: Float,
+    sin_theta: Float,
+    cos_theta: Float,
     bbox: Aabb,
 }
 
 impl RotateY {
-    pub fn new(object: Arc<dyn Hittable>, angle: f64) -> Self {
+    pub fn new(object: Arc<dyn Hittable>, angle: Float) -> Self {
         let (sin_theta, cos_theta) = angle.to_radians().sin_cos();
         let bbox = object.bounding_box();
 
-        let mut max = point3(f64::INFINITY, f64::INFINITY, f64::INFINITY);
-        let mut min = point3(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        let mut max = point3(Float::INFINITY, Float::INFINITY, Float::INFINITY);
+        let mut min = point3(
+            Float::NEG_INFINITY,
+            Float::NEG_INFINITY,
+            Float::NEG_INFINITY,
+        );
 
-        for i in (0..2).map(f64::from) {
-            for j in (0..2).map(f64::from) {
-                for k in (0..2).map(f64::from) {
+        for i in [0.0, 1.0] {
+            for j in [0.0, 1.0] {
+                for k in [0.0, 1.0] {
                     let x = i * bbox.x.max + (1.0 - i) * bbox.x.min;
                     let y = j * bbox.y.max + (1.0 - i) * bbox.y.min;
                     let z = k * bbox.z.max + (1.0 - i) * bbox.z.min;
@@ -58,38 +64,83 @@ impl RotateY {
     }
 }
 
+impl RotateY {
+    fn to_object_space(&self, v: Vec3) -> Vec3 {
+        vec3(
+            self.cos_theta * v.x - self.sin_theta * v.z,
+            v.y,
+            self.sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
+
+    // bounds the error of rotating p into world space, on top of the error p
+    // already had
+    fn rotated_error(&self, p: Point3, p_error: Vec3) -> Vec3 {
+        let (sin, cos) = (self.sin_theta.abs(), self.cos_theta.abs());
+        let abs_rotate = |v: Vec3| vec3(cos * v.x + sin * v.z, v.y, sin * v.x + cos * v.z);
+
+        abs_rotate(p_error) * (1.0 + gamma(3)) + abs_rotate(p.abs()) * gamma(3)
+    }
+
+    fn to_world_space(&self, v: Vec3) -> Vec3 {
+        vec3(
+            self.cos_theta * v.x + self.sin_theta * v.z,
+            v.y,
+            -self.sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
+
+    fn record_to_world_space(&self, mut rec: HitRecord) -> HitRecord {
+        rec.p_error = self.rotated_error(rec.p, rec.p_error);
+        rec.p = self.to_world_space(rec.p);
+        rec.normal = self.to_world_space(rec.normal);
+        rec.geometric_normal = self.to_world_space(rec.geometric_normal);
+        rec.tangent = self.to_world_space(rec.tangent);
+        rec.bitangent = self.to_world_space(rec.bitangent);
+        rec.dpdu = self.to_world_space(rec.dpdu);
+        rec.dpdv = self.to_world_space(rec.dpdv);
+        rec
+    }
+}
+
 impl Hittable for RotateY {
     fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
         // transform to object space
-        let orig = point3(
-            self.cos_theta * r.orig.x - self.sin_theta * r.orig.z,
-            r.orig.y,
-            self.sin_theta * r.orig.x + self.cos_theta * r.orig.z,
-        );
-
-        let dir = vec3(
-            self.cos_theta * r.dir.x - self.sin_theta * r.dir.z,
-            r.dir.y,
-            self.sin_theta * r.dir.x + self.cos_theta * r.dir.z,
+        let rotated_ray = ray(
+            self.to_object_space(r.orig),
+            self.to_object_space(r.dir),
+            r.time,
         );
 
-        let rotated_ray = ray(orig, dir, r.time);
-
         // check collision
 
-        let mut rec = self.object.hit(&rotated_ray, ray_t)?;
+        let rec = self.object.hit(&rotated_ray, ray_t)?;
 
-        rec.p = point3(
-            self.cos_theta * rec.p.x + self.sin_theta * rec.p.z,
-            rec.p.y,
-            -self.sin_theta * rec.p.x + self.cos_theta * rec.p.z,
-        );
-        rec.normal = point3(
-            self.cos_theta * rec.normal.x + self.sin_theta * rec.normal.z,
-            rec.normal.y,
-            -self.sin_theta * rec.normal.x + self.cos_theta * rec.normal.z,
+        Some(self.record_to_world_space(rec))
+    }
+
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        let rotated_rays = RayPacket::from_parts(
+            Vec3Packet::from_fn(|i| self.to_object_space(rays.orig.lane(i))),
+            Vec3Packet::from_fn(|i| self.to_object_space(rays.dir.lane(i))),
+            rays.time,
         );
-        Some(rec)
+
+        let mut rotated_hits = hits.narrowed();
+        self.object
+            .hit_packet(&rotated_rays, t_min, active, &mut rotated_hits);
+
+        for (lane, rec) in rotated_hits.recs.into_iter().enumerate() {
+            if let Some(rec) = rec {
+                hits.record(lane, self.record_to_world_space(rec));
+            }
+        }
     }
 
     fn bounding_box(&self) -> &crate::primitives::Aabb {
@@ -103,6 +154,6 @@ impl Display for RotateY {
     }
 }
 
-pub fn rotate_y(object: Arc<dyn Hittable>, angle: f64) -> Arc<RotateY> {
+pub fn rotate_y(object: Arc<dyn Hittable>, angle: Float) -> Arc<RotateY> {
     Arc::new(RotateY::new(object, angle))
 }
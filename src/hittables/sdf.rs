@@ -0,0 +1,237 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    materials::Material,
+    primitives::{Aabb, Float, Interval, Point3, Ray, Vec3, point3, vec3},
+};
+
+use super::{HitRecord, Hittable};
+
+/// A signed distance function: negative inside the shape, positive outside,
+/// and never more than the real distance to the surface.
+pub type DistanceFn = Arc<dyn Fn(Point3) -> Float + Send + Sync>;
+
+/// A surface given implicitly by a `DistanceFn`, found by sphere tracing.
+/// The function can't be bounded automatically, so `bbox` has to be given
+/// and must contain the whole surface.
+pub struct Sdf {
+    distance: DistanceFn,
+    epsilon: Float,
+    mat: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Sdf {
+    /// Gives up on rays that graze the surface for too long without hitting.
+    const MAX_STEPS: usize = 512;
+
+    pub fn new(distance: DistanceFn, bbox: Aabb, mat: Arc<dyn Material>) -> Self {
+        // close enough to count as a hit, relative to the size of the shape
+        // but never so small that steps get lost to rounding far from the
+        // origin
+        let (min, max) = (
+            point3(bbox.x.min, bbox.y.min, bbox.z.min),
+            point3(bbox.x.max, bbox.y.max, bbox.z.max),
+        );
+        let size = (max - min).length();
+        let magnitude = min.abs().max_component().max(max.abs().max_component());
+        let epsilon = (size * 1e-5).max(magnitude * 64.0 * Float::EPSILON);
+
+        Self {
+            distance,
+            epsilon,
+            mat,
+            bbox,
+        }
+    }
+
+    /// The gradient of the distance at `p`, sampled at the corners of a
+    /// tetrahedron, which takes four evaluations instead of six for central
+    /// differences.
+    fn normal(&self, p: Point3) -> Vec3 {
+        let h = self.epsilon;
+        let normal = [
+            vec3(1.0, -1.0, -1.0),
+            vec3(-1.0, -1.0, 1.0),
+            vec3(-1.0, 1.0, -1.0),
+            vec3(1.0, 1.0, 1.0),
+        ]
+        .into_iter()
+        .fold(Vec3::default(), |sum, k| {
+            sum + k * (self.distance)(p + k * h)
+        });
+
+        normal.unit_vector()
+    }
+
+    /// Sphere traces `r` from `t_min` to the first point within epsilon of
+    /// the surface. With `escape`, a march starting that close to the
+    /// surface first steps clear of it, so a hit found at `t` isn't found
+    /// again by continuing from `t`.
+    fn march(&self, r: &Ray, t_min: Float, t_max: Float, mut escape: bool) -> Option<HitRecord> {
+        let dir_length = r.dir.length();
+
+        // step by the distance to the closest surface, which can't skip past
+        // any of it. Rays leaving the surface from the inside march through
+        // negative distances, so only the magnitude matters
+        let mut t = t_min;
+        for _ in 0..Self::MAX_STEPS {
+            if t > t_max {
+                return None;
+            }
+
+            let p = r.at(t);
+            let distance = (self.distance)(p).abs();
+            if distance >= self.epsilon {
+                escape = false;
+            } else if escape {
+                t += self.epsilon / dir_length;
+                continue;
+            } else {
+                // the hit is only known to be within epsilon of the surface,
+                // and the error bound also keeps spawned rays from stopping
+                // on it straight away
+                let p_error = vec3(1.0, 1.0, 1.0) * (4.0 * self.epsilon);
+                let rec = HitRecord::new(p, self.mat.clone(), t, 0.0, 0.0, r, self.normal(p))
+                    .with_p_error(p_error);
+                return Some(rec);
+            }
+
+            t += distance / dir_length;
+        }
+
+        None
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let span = self.bbox.clip(r, ray_t)?;
+        self.march(r, span.min, span.max, false)
+    }
+
+    fn hit_all(&self, r: &Ray, ray_t: &Interval) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        let Some(span) = self.bbox.clip(r, ray_t) else {
+            return hits;
+        };
+
+        // only steps clear of the surface when continuing from a hit, so one
+        // right at the start of the range is still found
+        let mut t_min = span.min;
+        while let Some(rec) = self.march(r, t_min, span.max, !hits.is_empty()) {
+            t_min = rec.t;
+            hits.push(rec);
+        }
+
+        hits
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+impl Display for Sdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sdf({}, {})", self.bbox, self.mat)
+    }
+}
+
+pub fn sdf(distance: DistanceFn, bbox: Aabb, mat: Arc<dyn Material>) -> Arc<Sdf> {
+    Arc::new(Sdf::new(distance, bbox, mat))
+}
+
+#[must_use]
+pub fn sdf_sphere(center: Point3, radius: Float) -> DistanceFn {
+    Arc::new(move |p| (p - center).length() - radius)
+}
+
+/// A box reaching `half_extents` from its center along each axis.
+#[must_use]
+pub fn sdf_box(center: Point3, half_extents: Vec3) -> DistanceFn {
+    Arc::new(move |p| {
+        let d = p - center;
+        let q = d.abs() - half_extents;
+        let outside = vec3(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+        let inside = q.max_component().min(0.0);
+        outside + inside
+    })
+}
+
+/// A torus lying flat in the xz plane, with `major_radius` from the center
+/// to the middle of the tube.
+#[must_use]
+pub fn sdf_torus(center: Point3, major_radius: Float, minor_radius: Float) -> DistanceFn {
+    Arc::new(move |p| {
+        let d = p - center;
+        let ring = d.x.hypot(d.z) - major_radius;
+        ring.hypot(d.y) - minor_radius
+    })
+}
+
+/// A rounded cylinder between `a` and `b`.
+#[must_use]
+pub fn sdf_capsule(a: Point3, b: Point3, radius: Float) -> DistanceFn {
+    Arc::new(move |p| {
+        let (pa, ba) = (p - a, b - a);
+        let h = (pa.dot(ba) / ba.length_squared()).clamp(0.0, 1.0);
+        (pa - ba * h).length() - radius
+    })
+}
+
+#[must_use]
+pub fn sdf_union(a: DistanceFn, b: DistanceFn) -> DistanceFn {
+    Arc::new(move |p| a(p).min(b(p)))
+}
+
+/// A union that blends the shapes together wherever they come within
+/// `smoothness` of each other. Without any smoothness it's a plain union.
+#[must_use]
+pub fn sdf_smooth_union(a: DistanceFn, b: DistanceFn, smoothness: Float) -> DistanceFn {
+    if smoothness <= 0.0 {
+        return sdf_union(a, b);
+    }
+
+    Arc::new(move |p| {
+        let (da, db) = (a(p), b(p));
+        let h = (0.5 + 0.5 * (db - da) / smoothness).clamp(0.0, 1.0);
+        db + (da - db) * h - smoothness * h * (1.0 - h)
+    })
+}
+
+#[must_use]
+pub fn sdf_intersection(a: DistanceFn, b: DistanceFn) -> DistanceFn {
+    Arc::new(move |p| a(p).max(b(p)))
+}
+
+/// `a` with `b` cut out of it.
+#[must_use]
+pub fn sdf_subtraction(a: DistanceFn, b: DistanceFn) -> DistanceFn {
+    Arc::new(move |p| a(p).max(-b(p)))
+}
+
+/// Repeats `a` forever, once every `period` along each axis, with the copy
+/// at the origin left in place. A period of zero leaves that axis alone. The
+/// shape has to fit within a single period for the distance to stay valid.
+#[must_use]
+pub fn sdf_repeat(a: DistanceFn, period: Vec3) -> DistanceFn {
+    let wrap = |x: Float, period: Float| {
+        if period > 0.0 {
+            x - period * (x / period).round()
+        } else {
+            x
+        }
+    };
+
+    Arc::new(move |p| {
+        a(point3(
+            wrap(p.x, period.x),
+            wrap(p.y, period.y),
+            wrap(p.z, period.z),
+        ))
+    })
+}
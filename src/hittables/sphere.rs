@@ -1,24 +1,26 @@
-use std::f64::consts::PI;
 use std::fmt::{self, Display};
 use std::sync::Arc;
 
 use crate::{
     materials::Material,
-    primitives::{Aabb, Interval, Point3, Ray, vec3},
+    primitives::{
+        Aabb, Float, Interval, PACKET_WIDTH, PacketMask, Point3, Ray, RayPacket, Vec3, Vec3Packet,
+        consts::PI, gamma, interval, vec3,
+    },
 };
 
-use super::{HitRecord, Hittable};
+use super::{HitRecord, Hittable, PacketHits};
 
 #[derive(Clone)]
 pub struct Sphere {
     center: Point3,
-    radius: f64,
+    radius: Float,
     mat: Arc<dyn Material>,
     bbox: Aabb,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64, mat: Arc<dyn Material>) -> Self {
+    pub fn new(center: Point3, radius: Float, mat: Arc<dyn Material>) -> Self {
         let r_vec = vec3(radius, radius, radius);
         let bbox = Aabb::from_points(center - r_vec, center + r_vec);
         Self {
@@ -29,23 +31,9 @@ impl Sphere {
         }
     }
 
-    fn get_sphere_uv(p: Point3) -> (f64, f64) {
-        let theta = (-p.y).acos();
-        let phi = f64::atan2(-p.z, p.x) + PI;
-        let u = phi / (2.0 * PI);
-        let v = theta / PI;
-
-        (u, v)
-    }
-}
-
-impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
-        let oc = self.center - r.orig;
-        let a = r.dir.length_squared();
-        let h = r.dir.dot(oc);
-        let c = oc.length_squared() - self.radius * self.radius;
-
+    /// Solves the ray-sphere quadratic, returning the closest root within
+    /// `ray_t`.
+    fn nearest_root(a: Float, h: Float, c: Float, ray_t: &Interval) -> Option<Float> {
         let discriminant = h * h - a * c;
         if discriminant < 0.0 {
             return None;
@@ -63,21 +51,84 @@ impl Hittable for Sphere {
             }
         }
 
-        let rec_p = r.at(root);
+        Some(root)
+    }
+
+    #[allow(clippy::similar_names)]
+    fn hit_record(&self, r: &Ray, root: Float) -> HitRecord {
+        // project the hit point back onto the surface to undo the error from
+        // stepping along the ray
+        let offset = r.at(root) - self.center;
+        let offset = offset * (self.radius / offset.length());
+        let rec_p = self.center + offset;
+        let p_error = offset.abs() * gamma(5) + rec_p.abs() * gamma(1);
 
-        let outward_normal = (rec_p - self.center) / self.radius;
+        let outward_normal = offset / self.radius;
 
         let (u, v) = Sphere::get_sphere_uv(outward_normal);
+        let (dpdu, dpdv) = Sphere::get_sphere_derivatives(offset);
+
+        HitRecord::new(rec_p, self.mat.clone(), root, u, v, r, outward_normal)
+            .with_p_error(p_error)
+            .with_derivatives(dpdu, dpdv)
+    }
+
+    fn get_sphere_uv(p: Point3) -> (Float, Float) {
+        let theta = (-p.y).acos();
+        let phi = Float::atan2(-p.z, p.x) + PI;
+        let u = phi / (2.0 * PI);
+        let v = theta / PI;
+
+        (u, v)
+    }
+
+    /// Differentiates the mapping of `get_sphere_uv` at `offset` from the
+    /// center. Both are zero at the poles, where the mapping is singular.
+    #[allow(clippy::similar_names)]
+    fn get_sphere_derivatives(offset: Vec3) -> (Vec3, Vec3) {
+        let Vec3 { x, y, z } = offset;
+        let rho = x.hypot(z);
+        if rho == 0.0 {
+            return (Vec3::default(), Vec3::default());
+        }
+
+        let dpdu = vec3(z, 0.0, -x) * (2.0 * PI);
+        let dpdv = vec3(-x * y / rho, rho, -y * z / rho) * PI;
+
+        (dpdu, dpdv)
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let oc = self.center - r.orig;
+        let a = r.dir.length_squared();
+        let h = r.dir.dot(oc);
+        let c = oc.length_squared() - self.radius * self.radius;
 
-        Some(HitRecord::new(
-            rec_p,
-            self.mat.clone(),
-            root,
-            u,
-            v,
-            r,
-            outward_normal,
-        ))
+        let root = Self::nearest_root(a, h, c, ray_t)?;
+
+        Some(self.hit_record(r, root))
+    }
+
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        let oc = Vec3Packet::splat(self.center) - rays.orig;
+        let a = rays.dir.length_squared();
+        let h = rays.dir.dot(&oc);
+        let c = oc.length_squared().map(|l| l - self.radius * self.radius);
+
+        for lane in (0..PACKET_WIDTH).filter(|&lane| active[lane]) {
+            let ray_t = interval(t_min, hits.t_max[lane]);
+            if let Some(root) = Self::nearest_root(a[lane], h[lane], c[lane], &ray_t) {
+                hits.record(lane, self.hit_record(&rays.ray(lane), root));
+            }
+        }
     }
 
     fn bounding_box(&self) -> &Aabb {
@@ -91,6 +142,6 @@ impl Display for Sphere {
     }
 }
 
-pub fn sphere(center: Point3, radius: f64, mat: Arc<dyn Material>) -> Arc<Sphere> {
+pub fn sphere(center: Point3, radius: Float, mat: Arc<dyn Material>) -> Arc<Sphere> {
     Arc::new(Sphere::new(center, radius, mat))
 }
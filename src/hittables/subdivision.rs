@@ -0,0 +1,356 @@
+use std::{
+    array,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    primitives::{Float, Point3, Vec3},
+    tern,
+};
+
+/// The result of `loop_subdivide`, ready to become a `TriangleMesh`.
+pub(super) struct SubdividedMesh {
+    pub positions: Vec<Point3>,
+    pub indices: Vec<[u32; 3]>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Option<Vec<(Float, Float)>>,
+}
+
+/// An edge between two vertices, smallest index first.
+type Edge = (u32, u32);
+
+fn edge(a: u32, b: u32) -> Edge {
+    (a.min(b), a.max(b))
+}
+
+/// The faces around an edge, each with the corner opposite the edge.
+#[derive(Default)]
+struct EdgeFaces {
+    faces: Vec<(usize, u32)>,
+}
+
+/// Triangles over vertices that are shared wherever positions match, with
+/// texture coordinates kept per corner so seams survive subdivision.
+struct Topology {
+    points: Vec<Point3>,
+    faces: Vec<[u32; 3]>,
+    corner_uvs: Option<Vec<[(Float, Float); 3]>>,
+    creases: HashSet<Edge>,
+}
+
+impl Topology {
+    /// Every edge of the mesh, in the order the faces first reach them.
+    fn edges(&self) -> (Vec<Edge>, HashMap<Edge, EdgeFaces>) {
+        let mut order = Vec::new();
+        let mut edges: HashMap<Edge, EdgeFaces> = HashMap::new();
+
+        for (f, &[a, b, c]) in self.faces.iter().enumerate() {
+            for (from, to, opposite) in [(a, b, c), (b, c, a), (c, a, b)] {
+                let faces = edges.entry(edge(from, to)).or_insert_with(|| {
+                    order.push(edge(from, to));
+                    EdgeFaces::default()
+                });
+                faces.faces.push((f, opposite));
+            }
+        }
+
+        (order, edges)
+    }
+
+    /// Boundaries, creases and edges between more than two faces are kept
+    /// sharp, while edges inside a smooth manifold are smoothed over.
+    fn is_sharp(&self, e: Edge, faces: &EdgeFaces) -> bool {
+        faces.faces.len() != 2 || self.creases.contains(&e)
+    }
+
+    /// The neighbors of every vertex, and those of them across sharp edges.
+    fn neighbors(&self, order: &[Edge], edges: &HashMap<Edge, EdgeFaces>) -> Vec<Neighbors> {
+        let mut neighbors: Vec<Neighbors> = (0..self.points.len())
+            .map(|_| Neighbors::default())
+            .collect();
+
+        for &(a, b) in order {
+            let sharp = self.is_sharp((a, b), &edges[&(a, b)]);
+            for (from, to) in [(a, b), (b, a)] {
+                let n = &mut neighbors[from as usize];
+                n.all.push(to);
+                if sharp {
+                    n.sharp.push(to);
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /// One round of Loop subdivision, splitting every triangle in four.
+    fn subdivide(self) -> Self {
+        let (order, edges) = self.edges();
+        let neighbors = self.neighbors(&order, &edges);
+
+        // the old vertices keep their indices, moved towards their
+        // neighbors, and each edge gets a new vertex after them
+        let mut points: Vec<Point3> = self
+            .points
+            .iter()
+            .zip(&neighbors)
+            .map(|(&p, n)| n.refine(p, &self.points))
+            .collect();
+
+        let mut edge_points = HashMap::with_capacity(order.len());
+        for &(a, b) in &order {
+            let faces = &edges[&(a, b)];
+            let (pa, pb) = (self.points[a as usize], self.points[b as usize]);
+
+            let p = if self.is_sharp((a, b), faces) {
+                (pa + pb) * 0.5
+            } else {
+                let (pc, pd) = (
+                    self.points[faces.faces[0].1 as usize],
+                    self.points[faces.faces[1].1 as usize],
+                );
+                (pa + pb) * (3.0 / 8.0) + (pc + pd) * (1.0 / 8.0)
+            };
+
+            edge_points.insert((a, b), points.len() as u32);
+            points.push(p);
+        }
+
+        let mid = |a: u32, b: u32| edge_points[&edge(a, b)];
+        let faces = self
+            .faces
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (mid(a, b), mid(b, c), mid(c, a));
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect();
+
+        let corner_uvs = self.corner_uvs.map(|uvs| {
+            let avg = |(u0, v0): (Float, Float), (u1, v1): (Float, Float)| {
+                (u0.midpoint(u1), v0.midpoint(v1))
+            };
+            uvs.iter()
+                .flat_map(|&[a, b, c]| {
+                    let (ab, bc, ca) = (avg(a, b), avg(b, c), avg(c, a));
+                    [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+                })
+                .collect()
+        });
+
+        let creases = self
+            .creases
+            .iter()
+            .filter_map(|&(a, b)| edge_points.get(&(a, b)).map(|&m| [edge(a, m), edge(m, b)]))
+            .flatten()
+            .collect();
+
+        Self {
+            points,
+            faces,
+            corner_uvs,
+            creases,
+        }
+    }
+
+    /// Moves every vertex to where it would end up after subdividing forever.
+    fn push_to_limit(&mut self) {
+        let (order, edges) = self.edges();
+        let neighbors = self.neighbors(&order, &edges);
+
+        self.points = self
+            .points
+            .iter()
+            .zip(&neighbors)
+            .map(|(&p, n)| n.limit(p, &self.points))
+            .collect();
+    }
+
+    /// Smooth normals, split along sharp edges. Corners of neighboring
+    /// faces share a normal unless a sharp edge separates them, so every
+    /// corner is given the index of the group of corners it belongs to.
+    fn corner_normals(&self) -> (Vec<usize>, Vec<Vec3>) {
+        let (order, edges) = self.edges();
+        let corner = |f: usize, v: u32| f * 3 + self.faces[f].iter().position(|&i| i == v).unwrap();
+
+        let mut groups = UnionFind::new(self.faces.len() * 3);
+        for e in order {
+            let faces = &edges[&e];
+            if self.is_sharp(e, faces) {
+                continue;
+            }
+
+            let (f0, f1) = (faces.faces[0].0, faces.faces[1].0);
+            for v in [e.0, e.1] {
+                groups.union(corner(f0, v), corner(f1, v));
+            }
+        }
+
+        let mut normals = vec![Vec3::default(); self.faces.len() * 3];
+        let roots: Vec<usize> = (0..self.faces.len() * 3).map(|c| groups.find(c)).collect();
+        for (f, &[a, b, c]) in self.faces.iter().enumerate() {
+            let [pa, pb, pc] = [a, b, c].map(|i| self.points[i as usize]);
+
+            // weighted by area, so slivers don't skew it
+            let normal = (pb - pa).cross(pc - pa);
+            for k in 0..3 {
+                normals[roots[f * 3 + k]] += normal;
+            }
+        }
+
+        let normals = normals
+            .into_iter()
+            .map(|n| tern!(n.length_squared() > 0.0, n.unit_vector(), n))
+            .collect();
+        (roots, normals)
+    }
+}
+
+#[derive(Default)]
+struct Neighbors {
+    all: Vec<u32>,
+    sharp: Vec<u32>,
+}
+
+impl Neighbors {
+    fn sum(neighbors: &[u32], points: &[Point3]) -> Vec3 {
+        neighbors
+            .iter()
+            .fold(Vec3::default(), |sum, &i| sum + points[i as usize])
+    }
+
+    /// Where the vertex at `p` moves in one round of subdivision.
+    fn refine(&self, p: Point3, points: &[Point3]) -> Point3 {
+        match self.sharp.len() {
+            // a smooth vertex, or a dart where a crease fades out
+            0 | 1 if !self.all.is_empty() => {
+                let n = self.all.len() as Float;
+                let beta = tern!(self.all.len() == 3, 3.0 / 16.0, 3.0 / (8.0 * n));
+                p * (1.0 - n * beta) + Self::sum(&self.all, points) * beta
+            }
+            // on a crease or boundary, which is smoothed along itself only
+            2 => p * 0.75 + Self::sum(&self.sharp, points) * 0.125,
+            // a corner, where the creases meeting there pin it in place
+            _ => p,
+        }
+    }
+
+    /// Where the vertex at `p` ends up after subdividing forever.
+    fn limit(&self, p: Point3, points: &[Point3]) -> Point3 {
+        match self.sharp.len() {
+            0 | 1 if !self.all.is_empty() => {
+                let n = self.all.len() as Float;
+                let beta = tern!(self.all.len() == 3, 3.0 / 16.0, 3.0 / (8.0 * n));
+                let chi = 1.0 / (n + 3.0 / (8.0 * beta));
+                p * (1.0 - n * chi) + Self::sum(&self.all, points) * chi
+            }
+            2 => p * (2.0 / 3.0) + Self::sum(&self.sharp, points) * (1.0 / 6.0),
+            _ => p,
+        }
+    }
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+}
+
+/// Refines a triangle mesh with `levels` rounds of Loop subdivision, then
+/// moves it onto the limit surface. Vertices at the same position are
+/// treated as one, so meshes split along texture seams still subdivide
+/// smoothly across them. `creases` are edges, given by their vertex indices,
+/// that stay sharp like the boundaries of the mesh do.
+pub(super) fn loop_subdivide(
+    positions: &[Point3],
+    indices: &[[u32; 3]],
+    uvs: Option<&[(Float, Float)]>,
+    creases: &[[u32; 2]],
+    levels: u32,
+) -> SubdividedMesh {
+    let mut welded = HashMap::new();
+    let mut points = Vec::new();
+    let remap: Vec<u32> = positions
+        .iter()
+        .map(|p| {
+            let key = [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()];
+            *welded.entry(key).or_insert_with(|| {
+                points.push(*p);
+                points.len() as u32 - 1
+            })
+        })
+        .collect();
+
+    let mut topology = Topology {
+        points,
+        faces: indices
+            .iter()
+            .map(|face| face.map(|i| remap[i as usize]))
+            .collect(),
+        corner_uvs: uvs.map(|uvs| {
+            indices
+                .iter()
+                .map(|face| face.map(|i| uvs[i as usize]))
+                .collect()
+        }),
+        creases: creases
+            .iter()
+            .map(|&[a, b]| edge(remap[a as usize], remap[b as usize]))
+            .collect(),
+    };
+
+    for _ in 0..levels {
+        topology = topology.subdivide();
+    }
+    topology.push_to_limit();
+
+    // corners become shared vertices again wherever their position, normal
+    // and texture coordinates all agree
+    let (groups, group_normals) = topology.corner_normals();
+    let mut vertices = HashMap::new();
+    let mut mesh = SubdividedMesh {
+        positions: Vec::new(),
+        indices: Vec::with_capacity(topology.faces.len()),
+        normals: Vec::new(),
+        uvs: topology.corner_uvs.as_ref().map(|_| Vec::new()),
+    };
+
+    for (f, face) in topology.faces.iter().enumerate() {
+        let triangle = array::from_fn(|k| {
+            let group = groups[f * 3 + k];
+            let uv = topology.corner_uvs.as_ref().map(|uvs| uvs[f][k]);
+            let key = (group, uv.map(|(u, v)| (u.to_bits(), v.to_bits())));
+
+            *vertices.entry(key).or_insert_with(|| {
+                mesh.positions.push(topology.points[face[k] as usize]);
+                mesh.normals.push(group_normals[group]);
+                if let (Some(uvs), Some(uv)) = (&mut mesh.uvs, uv) {
+                    uvs.push(uv);
+                }
+                mesh.positions.len() as u32 - 1
+            })
+        });
+        mesh.indices.push(triangle);
+    }
+
+    mesh
+}
@@ -3,9 +3,11 @@ use std::{
     sync::Arc,
 };
 
-use crate::primitives::{Aabb, Interval, Ray, Vec3};
+use crate::primitives::{
+    Aabb, Float, Interval, PacketMask, Ray, RayPacket, Vec3, Vec3Packet, gamma,
+};
 
-use super::{HitRecord, Hittable};
+use super::{HitRecord, Hittable, PacketHits};
 
 pub struct Translate {
     object: Arc<dyn Hittable>,
@@ -32,10 +34,37 @@ impl Hittable for Translate {
         // check for a hit and move the hit position forward if there was a hit
         let mut rec = self.object.hit(&offset_ray, ray_t)?;
         rec.p += self.offset;
+        rec.p_error = rec.p_error * (1.0 + gamma(1)) + rec.p.abs() * gamma(1);
 
         Some(rec)
     }
 
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        let offset_rays = RayPacket::from_parts(
+            rays.orig - Vec3Packet::splat(self.offset),
+            rays.dir,
+            rays.time,
+        );
+
+        let mut offset_hits = hits.narrowed();
+        self.object
+            .hit_packet(&offset_rays, t_min, active, &mut offset_hits);
+
+        for (lane, rec) in offset_hits.recs.into_iter().enumerate() {
+            if let Some(mut rec) = rec {
+                rec.p += self.offset;
+                rec.p_error = rec.p_error * (1.0 + gamma(1)) + rec.p.abs() * gamma(1);
+                hits.record(lane, rec);
+            }
+        }
+    }
+
     fn bounding_box(&self) -> &Aabb {
         &self.bbox
     }
@@ -5,92 +5,271 @@ use std::{
 
 use crate::{
     materials::Material,
-    primitives::{Aabb, Interval, Point3, Ray, Vec3, point3},
+    primitives::{
+        Aabb, Float, Interval, PACKET_WIDTH, PacketMask, Point3, Ray, RayPacket, RayShear, Vec3,
+        gamma, interval, point3, vec3,
+    },
+    tern,
 };
 
-use super::{HitRecord, Hittable};
+use super::{HitRecord, Hittable, PacketHits};
 
 #[derive(Clone)]
 pub struct Triangle {
     a: Point3,
     b: Point3,
     c: Point3,
-    edge1: Vec3,
-    edge2: Vec3,
-    outward_normal: Vec3,
+    attributes: VertexAttributes,
     mat: Arc<dyn Material>,
     bbox: Aabb,
 }
 
 impl Triangle {
     pub fn new(a: Point3, b: Point3, c: Point3, material: Arc<dyn Material>) -> Self {
-        let edge1 = b - a;
-        let edge2 = c - a;
-        let outward_normal = edge1.cross(edge2).unit_vector();
-
-        let min = point3(
-            a.x.min(b.x).min(c.x),
-            a.y.min(b.y).min(c.y),
-            a.z.min(b.z).min(c.z),
-        );
-        let max = point3(
-            a.x.max(b.x).max(c.x),
-            a.y.max(b.y).max(c.y),
-            a.z.max(b.z).max(c.z),
-        );
-        let bbox = Aabb::from_points(min, max);
-
         Self {
             a,
             b,
             c,
-            edge1,
-            edge2,
-            outward_normal,
+            attributes: VertexAttributes::default(),
             mat: material,
-            bbox,
+            bbox: triangle_bbox([a, b, c]),
         }
     }
+
+    /// Shades the triangle smoothly with normals given at `a`, `b` and `c`.
+    #[must_use]
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.attributes.normals = Some(normals);
+        self
+    }
+
+    /// Gives texture coordinates at `a`, `b` and `c`. Without them, the
+    /// barycentrics of the hit are reported as `u` and `v`.
+    #[must_use]
+    pub fn with_uvs(mut self, uvs: [(Float, Float); 3]) -> Self {
+        self.attributes.uvs = Some(uvs);
+        self
+    }
+
+    /// Gives tangents at `a`, `b` and `c`, along with the sign of their
+    /// bitangents relative to normal x tangent, overriding the frame derived
+    /// from the texture coordinates.
+    #[must_use]
+    pub fn with_tangents(mut self, tangents: [(Vec3, Float); 3]) -> Self {
+        self.attributes.tangents = Some(tangents);
+        self
+    }
+
+    fn hit_record(&self, r: &Ray, hit: &TriangleHit) -> HitRecord {
+        hit.hit_record(
+            r,
+            self.mat.clone(),
+            [self.a, self.b, self.c],
+            &self.attributes,
+        )
+    }
 }
 
-impl Hittable for Triangle {
-    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
-        let ray_cross_e2 = r.dir.cross(self.edge2);
-        let denom = self.edge1.dot(ray_cross_e2);
+pub(super) fn triangle_bbox([a, b, c]: [Point3; 3]) -> Aabb {
+    let min = point3(
+        a.x.min(b.x).min(c.x),
+        a.y.min(b.y).min(c.y),
+        a.z.min(b.z).min(c.z),
+    );
+    let max = point3(
+        a.x.max(b.x).max(c.x),
+        a.y.max(b.y).max(c.y),
+        a.z.max(b.z).max(c.z),
+    );
+    Aabb::from_points(min, max)
+}
 
-        if denom.abs() < 1e-8 {
-            return None;
-        }
+/// The optional per-vertex data of a triangle.
+#[derive(Clone, Default)]
+pub(super) struct VertexAttributes {
+    pub normals: Option<[Vec3; 3]>,
+    pub uvs: Option<[(Float, Float); 3]>,
+    pub tangents: Option<[(Vec3, Float); 3]>,
+}
 
-        let s = r.orig - self.a;
-        let u = s.dot(ray_cross_e2) / denom;
-        if !(0.0..=1.0).contains(&u) {
-            return None;
-        }
+pub(super) struct TriangleHit {
+    pub t: Float,
+    pub barycentrics: [Float; 3],
+    pub p: Point3,
+    pub p_error: Vec3,
+}
+
+impl TriangleHit {
+    /// Builds the hit record, interpolating whichever vertex attributes the
+    /// triangle has.
+    #[allow(clippy::similar_names)]
+    pub fn hit_record(
+        &self,
+        r: &Ray,
+        mat: Arc<dyn Material>,
+        [p0, p1, p2]: [Point3; 3],
+        attributes: &VertexAttributes,
+    ) -> HitRecord {
+        let [b0, b1, b2] = self.barycentrics;
+        let interpolate = |[a, b, c]: [Vec3; 3]| a * b0 + b * b1 + c * b2;
 
-        let s_cross_e1 = s.cross(self.edge1);
-        let v = r.dir.dot(s_cross_e1) / denom;
-        if v < 0.0 || u + v > 1.0 {
-            return None;
+        // without texture coordinates, the barycentrics double as them
+        let uvs = attributes
+            .uvs
+            .unwrap_or([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+        let [uv0, uv1, uv2] = uvs;
+        let u = b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0;
+        let v = b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1;
+
+        // solve for the derivatives from the differences across two edges
+        let (du02, dv02) = (uv0.0 - uv2.0, uv0.1 - uv2.1);
+        let (du12, dv12) = (uv1.0 - uv2.0, uv1.1 - uv2.1);
+        let (dp02, dp12) = (p0 - p2, p1 - p2);
+        let det = du02 * dv12 - dv02 * du12;
+        let (mut dpdu, mut dpdv) = if det.abs() < 1e-9 {
+            (Vec3::default(), Vec3::default())
+        } else {
+            let inv_det = 1.0 / det;
+            (
+                (dp02 * dv12 - dp12 * dv02) * inv_det,
+                (dp12 * du02 - dp02 * du12) * inv_det,
+            )
+        };
+
+        let outward_normal = (p1 - p0).cross(p2 - p0).unit_vector();
+        if let Some(tangents) = attributes.tangents
+            && let tangent = interpolate(tangents.map(|(t, _)| t))
+            && let bitangent = outward_normal.cross(tangent)
+            && !bitangent.is_near_zero()
+        {
+            // the sign keeps mirrored texture coordinates from flipping the
+            // bitangent
+            let sign = b0 * tangents[0].1 + b1 * tangents[1].1 + b2 * tangents[2].1;
+            let length = |d: Vec3| tern!(d.is_near_zero(), 1.0, d.length());
+            dpdu = tangent.unit_vector() * length(dpdu);
+            let bitangent = bitangent.unit_vector() * length(dpdv);
+            dpdv = tern!(sign < 0.0, -bitangent, bitangent);
         }
 
-        let t = self.edge2.dot(s_cross_e1) / denom;
+        let rec = HitRecord::new(self.p, mat, self.t, u, v, r, outward_normal)
+            .with_p_error(self.p_error)
+            .with_derivatives(dpdu, dpdv);
 
-        if !(ray_t.surrounds(t)) {
-            return None;
+        // the vertex normals can cancel out, in which case the flat normal is
+        // the best there is
+        match attributes.normals.map(interpolate) {
+            Some(n) if !n.is_near_zero() => rec.with_shading_normal(n.unit_vector()),
+            _ => rec,
         }
+    }
+}
 
-        let p = r.at(t);
+/// Watertight ray-triangle intersection (Woop, Benthin and Wald 2013). Rays
+/// passing exactly through a shared edge or vertex always hit at least one of
+/// the triangles around it, so meshes can't leak light through cracks.
+pub(super) fn intersect_triangle(
+    [p0, p1, p2]: [Point3; 3],
+    orig: Point3,
+    shear: &RayShear,
+    ray_t: &Interval,
+) -> Option<TriangleHit> {
+    // move the vertices into a space where the ray starts at the origin and
+    // points down +z
+    let p0t = shear.apply(p0 - orig);
+    let p1t = shear.apply(p1 - orig);
+    let p2t = shear.apply(p2 - orig);
 
-        Some(HitRecord::new(
-            p,
-            self.mat.clone(),
-            t,
-            u,
-            v,
-            r,
-            self.outward_normal,
-        ))
+    // the edge functions say which side of each edge the ray passes
+    let e0 = p1t.x * p2t.y - p1t.y * p2t.x;
+    let e1 = p2t.x * p0t.y - p2t.y * p0t.x;
+    let e2 = p0t.x * p1t.y - p0t.y * p1t.x;
+
+    if (e0 < 0.0 || e1 < 0.0 || e2 < 0.0) && (e0 > 0.0 || e1 > 0.0 || e2 > 0.0) {
+        return None;
+    }
+
+    let det = e0 + e1 + e2;
+    if det == 0.0 {
+        return None;
+    }
+
+    // compute the hit distance scaled by det, to defer the division
+    let z = vec3(p0t.z, p1t.z, p2t.z) * shear.shear.z;
+    let t_scaled = e0 * z.x + e1 * z.y + e2 * z.z;
+
+    let (t_lo, t_hi) = (ray_t.min * det, ray_t.max * det);
+    if det < 0.0 && (t_scaled >= t_lo || t_scaled < t_hi) {
+        return None;
+    }
+    if det > 0.0 && (t_scaled <= t_lo || t_scaled > t_hi) {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let barycentrics = [e0 * inv_det, e1 * inv_det, e2 * inv_det];
+    let t = t_scaled * inv_det;
+
+    // make sure t is far enough past the origin to not be rounding error,
+    // unless the caller asked for hits behind it too
+    let max_t = vec3(
+        vec3(p0t.x, p1t.x, p2t.x).abs().max_component(),
+        vec3(p0t.y, p1t.y, p2t.y).abs().max_component(),
+        z.abs().max_component(),
+    );
+    let delta_x = gamma(5) * (max_t.x + max_t.z);
+    let delta_y = gamma(5) * (max_t.y + max_t.z);
+    let delta_z = gamma(3) * max_t.z;
+
+    let delta_e = 2.0 * (gamma(2) * max_t.x * max_t.y + delta_y * max_t.x + delta_x * max_t.y);
+    let max_e = vec3(e0, e1, e2).abs().max_component();
+
+    let delta_t =
+        3.0 * (gamma(3) * max_e * max_t.z + delta_e * max_t.z + delta_z * max_e) * inv_det.abs();
+    if ray_t.min >= 0.0 && t <= delta_t {
+        return None;
+    }
+
+    // interpolate the hit point from the vertices rather than stepping along
+    // the ray, which has much tighter error bounds
+    let [b0, b1, b2] = barycentrics;
+    let p = p0 * b0 + p1 * b1 + p2 * b2;
+    let p_error = ((p0 * b0).abs() + (p1 * b1).abs() + (p2 * b2).abs()) * gamma(7);
+
+    Some(TriangleHit {
+        t,
+        barycentrics,
+        p,
+        p_error,
+    })
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let corners = [self.a, self.b, self.c];
+        let hit = intersect_triangle(corners, r.orig, &RayShear::new(r.dir), ray_t)?;
+
+        Some(self.hit_record(r, &hit))
+    }
+
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        let corners = [self.a, self.b, self.c];
+
+        // the shears were computed once for the packet, rather than once per
+        // triangle
+        for lane in (0..PACKET_WIDTH).filter(|&lane| active[lane]) {
+            let ray_t = interval(t_min, hits.t_max[lane]);
+            let orig = rays.orig.lane(lane);
+
+            if let Some(hit) = intersect_triangle(corners, orig, &rays.shear[lane], &ray_t) {
+                hits.record(lane, self.hit_record(&rays.ray(lane), &hit));
+            }
+        }
     }
 
     fn bounding_box(&self) -> &Aabb {
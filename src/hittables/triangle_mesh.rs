@@ -0,0 +1,246 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    materials::Material,
+    primitives::{
+        Aabb, Float, Interval, PACKET_WIDTH, PacketMask, Point3, Ray, RayPacket, RayShear, Vec3,
+        interval,
+    },
+    textures::Texture,
+};
+
+use super::{
+    HitRecord, Hittable, PacketHits, TriangleHit, VertexAttributes, displacement::displace,
+    intersect_triangle, subdivision::loop_subdivide, triangle_bbox,
+};
+
+/// Vertex and index buffers shared by every triangle of a mesh. Vertices
+/// shared between triangles are stored once, and the per-vertex attributes
+/// are optional so meshes only pay for what they were loaded with.
+pub struct TriangleMesh {
+    positions: Vec<Point3>,
+    indices: Vec<[u32; 3]>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(Float, Float)>>,
+    tangents: Option<Vec<(Vec3, Float)>>,
+    mat: Arc<dyn Material>,
+}
+
+impl TriangleMesh {
+    /// # Panics
+    /// If any index is out of bounds of `positions`.
+    pub fn new(positions: Vec<Point3>, indices: Vec<[u32; 3]>, mat: Arc<dyn Material>) -> Self {
+        assert!(
+            indices
+                .iter()
+                .flatten()
+                .all(|&i| (i as usize) < positions.len()),
+            "mesh index out of bounds"
+        );
+
+        Self {
+            positions,
+            indices,
+            normals: None,
+            uvs: None,
+            tangents: None,
+            mat,
+        }
+    }
+
+    /// # Panics
+    /// If there isn't exactly one normal per vertex.
+    #[must_use]
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Self {
+        assert_eq!(normals.len(), self.positions.len());
+        self.normals = Some(normals);
+        self
+    }
+
+    /// # Panics
+    /// If there isn't exactly one uv per vertex.
+    #[must_use]
+    pub fn with_uvs(mut self, uvs: Vec<(Float, Float)>) -> Self {
+        assert_eq!(uvs.len(), self.positions.len());
+        self.uvs = Some(uvs);
+        self
+    }
+
+    /// Takes each vertex's tangent along with the sign of its bitangent
+    /// relative to normal x tangent, overriding the frame derived from the
+    /// texture coordinates.
+    ///
+    /// # Panics
+    /// If there isn't exactly one tangent per vertex.
+    #[must_use]
+    pub fn with_tangents(mut self, tangents: Vec<(Vec3, Float)>) -> Self {
+        assert_eq!(tangents.len(), self.positions.len());
+        self.tangents = Some(tangents);
+        self
+    }
+
+    #[must_use]
+    pub fn positions(&self) -> &[Point3] {
+        &self.positions
+    }
+
+    #[must_use]
+    pub fn indices(&self) -> &[[u32; 3]] {
+        &self.indices
+    }
+
+    #[must_use]
+    pub fn normals(&self) -> Option<&[Vec3]> {
+        self.normals.as_deref()
+    }
+
+    #[must_use]
+    pub fn uvs(&self) -> Option<&[(Float, Float)]> {
+        self.uvs.as_deref()
+    }
+
+    #[must_use]
+    pub fn tangents(&self) -> Option<&[(Vec3, Float)]> {
+        self.tangents.as_deref()
+    }
+
+    /// Smooths the mesh with `levels` rounds of Loop subdivision, each of
+    /// which splits every triangle in four. `creases` lists edges, by the
+    /// indices of their vertices, that should stay sharp, as the boundaries
+    /// of the mesh do. Normals are recomputed from the smoothed surface and
+    /// any tangents are dropped.
+    #[must_use]
+    pub fn subdivide(self, levels: u32, creases: &[[u32; 2]]) -> Self {
+        if levels == 0 {
+            return self;
+        }
+
+        let refined = loop_subdivide(
+            &self.positions,
+            &self.indices,
+            self.uvs.as_deref(),
+            creases,
+            levels,
+        );
+
+        let mesh =
+            Self::new(refined.positions, refined.indices, self.mat).with_normals(refined.normals);
+        match refined.uvs {
+            Some(uvs) => mesh.with_uvs(uvs),
+            None => mesh,
+        }
+    }
+
+    /// Gives the mesh real relief by splitting every triangle in four
+    /// `levels` times, then moving the vertices along their normals by the
+    /// brightness of `height` times `scale`. Normals are recomputed from the
+    /// displaced surface and any tangents are dropped.
+    #[must_use]
+    pub fn displace(self, height: &dyn Texture, scale: Float, levels: u32) -> Self {
+        let displaced = displace(
+            &self.positions,
+            &self.indices,
+            self.normals.as_deref(),
+            self.uvs.as_deref(),
+            height,
+            scale,
+            levels,
+        );
+
+        let mesh = Self::new(displaced.positions, displaced.indices, self.mat)
+            .with_normals(displaced.normals);
+        match displaced.uvs {
+            Some(uvs) => mesh.with_uvs(uvs),
+            None => mesh,
+        }
+    }
+
+    /// Splits the mesh into one hittable per triangle, for a BVH to be built
+    /// over. The triangles only hold on to the shared mesh and their index.
+    pub fn into_triangles(self) -> impl Iterator<Item = Arc<MeshTriangle>> {
+        let mesh = Arc::new(self);
+        (0..mesh.indices.len()).map(move |index| Arc::new(MeshTriangle::new(mesh.clone(), index)))
+    }
+}
+
+/// A reference to one triangle of a `TriangleMesh`.
+pub struct MeshTriangle {
+    mesh: Arc<TriangleMesh>,
+    index: u32,
+    bbox: Aabb,
+}
+
+impl MeshTriangle {
+    /// # Panics
+    /// If the mesh doesn't have a triangle at `index`.
+    #[must_use]
+    pub fn new(mesh: Arc<TriangleMesh>, index: usize) -> Self {
+        let mut tri = Self {
+            mesh,
+            index: u32::try_from(index).expect("mesh has too many triangles"),
+            bbox: Aabb::default(),
+        };
+        tri.bbox = triangle_bbox(tri.vertices());
+        tri
+    }
+
+    fn vertex_indices(&self) -> [usize; 3] {
+        self.mesh.indices[self.index as usize].map(|i| i as usize)
+    }
+
+    fn vertices(&self) -> [Point3; 3] {
+        self.vertex_indices().map(|i| self.mesh.positions[i])
+    }
+
+    fn hit_record(&self, r: &Ray, hit: &TriangleHit) -> HitRecord {
+        let indices = self.vertex_indices();
+        let attributes = VertexAttributes {
+            normals: self.mesh.normals().map(|n| indices.map(|i| n[i])),
+            uvs: self.mesh.uvs().map(|uv| indices.map(|i| uv[i])),
+            tangents: self.mesh.tangents().map(|t| indices.map(|i| t[i])),
+        };
+
+        hit.hit_record(r, self.mesh.mat.clone(), self.vertices(), &attributes)
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, r: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+        let hit = intersect_triangle(self.vertices(), r.orig, &RayShear::new(r.dir), ray_t)?;
+
+        Some(self.hit_record(r, &hit))
+    }
+
+    fn hit_packet(
+        &self,
+        rays: &RayPacket,
+        t_min: Float,
+        active: PacketMask,
+        hits: &mut PacketHits,
+    ) {
+        let corners = self.vertices();
+
+        for lane in (0..PACKET_WIDTH).filter(|&lane| active[lane]) {
+            let ray_t = interval(t_min, hits.t_max[lane]);
+            let orig = rays.orig.lane(lane);
+
+            if let Some(hit) = intersect_triangle(corners, orig, &rays.shear[lane], &ray_t) {
+                hits.record(lane, self.hit_record(&rays.ray(lane), &hit));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+}
+
+impl Display for MeshTriangle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c] = self.vertices();
+        write!(f, "mesh_triangle({}, {a}, {b}, {c})", self.index)
+    }
+}
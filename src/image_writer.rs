@@ -5,7 +5,7 @@ use std::{
     path::Path,
 };
 
-use crate::primitives::Color;
+use crate::primitives::{Color, Float};
 
 pub trait ImageWriter<T>: Sized {
     fn new(data: T, width: usize, height: usize) -> Result<Self, Box<dyn Error>>;
@@ -86,7 +86,7 @@ impl<T: AsRef<Path>> ImageWriter<T> for PNGImageWriter {
     }
 }
 
-fn linear_to_gamma(linear_component: f64) -> f64 {
+fn linear_to_gamma(linear_component: Float) -> Float {
     if linear_component > 0.0 {
         linear_component.powf(1.0 / 2.2)
     } else {
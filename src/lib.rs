@@ -17,6 +17,6 @@ pub mod hittables;
 pub mod image_writer;
 pub mod materials;
 pub mod misc;
+pub mod pdfs;
 pub mod primitives;
 pub mod textures;
-pub mod pdfs;
@@ -24,6 +24,6 @@ fn main() {
     // run(simple_light(), "simple_light");
     run(cornell_box(), "cornel_box");
     // run(triangles(), "triangles");
-    // run(room(), "room");
+    // run(room(&MeshDetail::default()), "room");
     // run(book_2_final(), "book_2_final");
 }
@@ -0,0 +1,119 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    hittables::HitRecord,
+    primitives::{Color, Float, Medium, PathState, Point3, Ray, Vec3},
+    tern,
+    textures::Texture,
+};
+
+use super::{Material, ScatterRecord};
+
+/// How far apart, in texture space, the height is sampled to find its slope.
+const BUMP_DELTA: Float = 0.0005;
+
+/// Bends the shading normal of another material as if the surface were
+/// displaced along its normal by a height texture. The brightness of the
+/// texture, times `scale`, gives the height.
+pub struct BumpMapped {
+    material: Arc<dyn Material>,
+    height: Arc<dyn Texture>,
+    scale: Float,
+}
+
+impl BumpMapped {
+    pub fn new(material: Arc<dyn Material>, height: Arc<dyn Texture>, scale: Float) -> Self {
+        Self {
+            material,
+            height,
+            scale,
+        }
+    }
+}
+
+impl Material for BumpMapped {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        self.material.scatter(r, rec, path)
+    }
+
+    fn emitted(
+        &self,
+        r: &Ray,
+        rec: &HitRecord,
+        u: Float,
+        v: Float,
+        p: Point3,
+        path: &PathState,
+    ) -> Color {
+        self.material.emitted(r, rec, u, v, p, path)
+    }
+
+    #[allow(clippy::similar_names)]
+    fn perturbed_normal(&self, rec: &HitRecord) -> Option<Vec3> {
+        // surfaces without a parameterization are stepped along an arbitrary
+        // unit frame instead
+        let (dpdu, dpdv) = if rec.dpdu.is_near_zero() || rec.dpdv.is_near_zero() {
+            (rec.tangent, rec.bitangent)
+        } else {
+            (rec.dpdu, rec.dpdv)
+        };
+
+        let height = |du: Float, dv: Float| {
+            let p = rec.p + dpdu * du + dpdv * dv;
+            self.height.value(rec.u + du, rec.v + dv, p).average() * self.scale
+        };
+
+        let h = height(0.0, 0.0);
+        let dhdu = (height(BUMP_DELTA, 0.0) - h) / BUMP_DELTA;
+        let dhdv = (height(0.0, BUMP_DELTA) - h) / BUMP_DELTA;
+
+        let normal = (dpdu + rec.normal * dhdu).cross(dpdv + rec.normal * dhdv);
+
+        tern!(normal.is_near_zero(), None, Some(normal.unit_vector()))
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Float {
+        self.material.scattering_pdf(r_in, rec, scattered, path)
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        self.material.scattering_color(r_in, rec, scattered, path)
+    }
+
+    fn interior(&self) -> Option<Medium> {
+        self.material.interior()
+    }
+}
+
+impl Display for BumpMapped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bump_mapped({}, {}, {})",
+            self.material, self.height, self.scale
+        )
+    }
+}
+
+pub fn bump_mapped(
+    material: Arc<dyn Material>,
+    height: Arc<dyn Texture>,
+    scale: Float,
+) -> Arc<BumpMapped> {
+    Arc::new(BumpMapped::new(material, height, scale))
+}
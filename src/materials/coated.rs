@@ -0,0 +1,130 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    hittables::HitRecord,
+    misc::rand_float,
+    primitives::{Color, Float, Medium, PathState, Point3, Ray, Vec3, color},
+};
+
+use super::{
+    Material, ScatterRecord,
+    microfacet::{TrowbridgeReitz, fresnel_dielectric, scatter_reflection},
+};
+
+/// A clear dielectric coat over any other material, like the lacquer on car
+/// paint or the varnish on a wooden floor. Light is reflected off the coat
+/// as much as its Fresnel reflectance says, and only what gets through it,
+/// on the way in and again on the way out, reaches the base and comes back.
+/// The coat is thin enough that it doesn't bend the light reaching the base.
+pub struct Coated {
+    base: Arc<dyn Material>,
+    refraction_index: Float,
+    distribution: TrowbridgeReitz,
+}
+
+impl Coated {
+    /// `roughness` is in `[0, 1]`, from a glossy coat to a satin one.
+    #[must_use]
+    pub fn new(base: Arc<dyn Material>, refraction_index: Float, roughness: Float) -> Self {
+        Self {
+            base,
+            refraction_index,
+            distribution: TrowbridgeReitz::new(roughness, roughness),
+        }
+    }
+
+    /// How much of the light crossing the coat at `cos_theta` to the normal
+    /// it reflects.
+    fn reflectance(&self, cos_theta: Float) -> Float {
+        fresnel_dielectric(cos_theta.abs(), self.refraction_index)
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        // the coat is picked as often as it reflects, which cancels the
+        // light lost to it on the way into the base
+        let coat = self.reflectance(wo.z);
+        if rand_float() < coat {
+            return scatter_reflection(self.distribution, r, rec, coat, |cos_theta| {
+                let f = self.reflectance(cos_theta);
+                color(f, f, f)
+            });
+        }
+
+        match self.base.scatter(r, rec, path)? {
+            ScatterRecord::SkipPdf { attenuation, ray } => {
+                let cos_theta = rec.normal.dot(ray.dir.unit_vector());
+                Some(ScatterRecord::SkipPdf {
+                    attenuation: attenuation * (1.0 - self.reflectance(cos_theta)),
+                    ray,
+                })
+            }
+            // the way out is accounted for in `scattering_color`
+            scatter @ ScatterRecord::Pdf { .. } => Some(scatter),
+        }
+    }
+
+    fn emitted(
+        &self,
+        r: &Ray,
+        rec: &HitRecord,
+        u: Float,
+        v: Float,
+        p: Point3,
+        path: &PathState,
+    ) -> Color {
+        let cos_theta = rec.normal.dot(r.dir.unit_vector());
+        self.base.emitted(r, rec, u, v, p, path) * (1.0 - self.reflectance(cos_theta))
+    }
+
+    fn perturbed_normal(&self, rec: &HitRecord) -> Option<Vec3> {
+        self.base.perturbed_normal(rec)
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Float {
+        self.base.scattering_pdf(r_in, rec, scattered, path)
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        let cos_theta = rec.normal.dot(scattered.dir.unit_vector());
+        self.base.scattering_color(r_in, rec, scattered, path) * (1.0 - self.reflectance(cos_theta))
+    }
+
+    fn interior(&self) -> Option<Medium> {
+        self.base.interior()
+    }
+}
+
+impl fmt::Display for Coated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "coated({}, {}, {:?})",
+            self.base, self.refraction_index, self.distribution
+        )
+    }
+}
+
+#[must_use]
+pub fn coated(base: Arc<dyn Material>, refraction_index: Float, roughness: Float) -> Arc<Coated> {
+    Arc::new(Coated::new(base, refraction_index, roughness))
+}
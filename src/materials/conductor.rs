@@ -0,0 +1,168 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    hittables::HitRecord,
+    primitives::{Color, Float, PathState, Ray, color},
+};
+
+use super::{
+    Material, ScatterRecord,
+    microfacet::{MicrofacetReflectionPdf, TrowbridgeReitz, fresnel_conductor, reflection_pdf},
+};
+
+/// A metal with microfacets following the GGX distribution, whose color
+/// comes from its complex index of refraction `eta + i k`.
+#[derive(Clone)]
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    distribution: TrowbridgeReitz,
+}
+
+impl Conductor {
+    /// `roughness` is in `[0, 1]`, from a perfect mirror to a dull finish.
+    #[must_use]
+    pub fn new(eta: Color, k: Color, roughness: Float) -> Self {
+        Self::anisotropic(eta, k, roughness, roughness)
+    }
+
+    /// A conductor rough differently along the tangent, `u`, and the
+    /// bitangent, `v`, as brushed metal is.
+    #[must_use]
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: Float, roughness_v: Float) -> Self {
+        Self {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::new(roughness_u, roughness_v),
+        }
+    }
+
+    #[must_use]
+    pub fn gold(roughness: Float) -> Self {
+        Self::new(
+            color(0.143, 0.374, 1.442),
+            color(3.983, 2.386, 1.603),
+            roughness,
+        )
+    }
+
+    #[must_use]
+    pub fn copper(roughness: Float) -> Self {
+        Self::new(
+            color(0.200, 0.924, 1.102),
+            color(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    #[must_use]
+    pub fn aluminum(roughness: Float) -> Self {
+        Self::new(
+            color(1.657, 0.880, 0.521),
+            color(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        if self.distribution.is_smooth() {
+            let dir = r.dir.reflect(rec.normal);
+            return rec.is_reflection(dir).then(|| ScatterRecord::SkipPdf {
+                attenuation: path.spectrum(fresnel_conductor(wo.z, self.eta, self.k)),
+                ray: rec.spawn_ray(dir, r.time),
+            });
+        }
+
+        Some(ScatterRecord::Pdf {
+            attenuation: color(1.0, 1.0, 1.0),
+            pdf: Box::new(MicrofacetReflectionPdf::new(frame, wo, self.distribution)),
+        })
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        _path: &PathState,
+    ) -> Float {
+        if !rec.is_reflection(scattered.dir) {
+            return 0.0;
+        }
+
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r_in.dir.unit_vector());
+        let wi = frame.to_local(scattered.dir.unit_vector());
+        reflection_pdf(self.distribution, wo, wi)
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r_in.dir.unit_vector());
+        let wi = frame.to_local(scattered.dir.unit_vector());
+        if !rec.is_reflection(scattered.dir) || wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::default();
+        }
+
+        // the cosine of wi cancels with the one in the BRDF's denominator
+        let wm = (wo + wi).unit_vector();
+        let fresnel = fresnel_conductor(wo.dot(wm), self.eta, self.k);
+        path.spectrum(
+            fresnel * (self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * wo.z)),
+        )
+    }
+}
+
+impl fmt::Display for Conductor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "conductor({}, {}, {:?})",
+            self.eta, self.k, self.distribution
+        )
+    }
+}
+
+#[must_use]
+pub fn conductor(eta: Color, k: Color, roughness: Float) -> Arc<Conductor> {
+    Arc::new(Conductor::new(eta, k, roughness))
+}
+
+#[must_use]
+pub fn anisotropic_conductor(
+    eta: Color,
+    k: Color,
+    roughness_u: Float,
+    roughness_v: Float,
+) -> Arc<Conductor> {
+    Arc::new(Conductor::anisotropic(eta, k, roughness_u, roughness_v))
+}
+
+#[must_use]
+pub fn gold(roughness: Float) -> Arc<Conductor> {
+    Arc::new(Conductor::gold(roughness))
+}
+
+#[must_use]
+pub fn copper(roughness: Float) -> Arc<Conductor> {
+    Arc::new(Conductor::copper(roughness))
+}
+
+#[must_use]
+pub fn aluminum(roughness: Float) -> Arc<Conductor> {
+    Arc::new(Conductor::aluminum(roughness))
+}
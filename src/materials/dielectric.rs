@@ -2,38 +2,57 @@ use std::{fmt, sync::Arc};
 
 use crate::{
     hittables::HitRecord,
-    misc::rand_f64,
-    primitives::{Color, Ray, color, ray},
+    misc::rand_float,
+    primitives::{Color, Dispersion, Float, Medium, PathState, Ray, color},
     tern,
 };
 
-use super::Material;
+use super::{Material, ScatterRecord};
 
 #[derive(Clone)]
 pub struct Dielectric {
-    refraction_index: f64,
+    medium: Medium,
 }
 
 impl Dielectric {
     #[must_use]
-    pub fn new(refraction_index: f64) -> Self {
-        Self { refraction_index }
+    pub fn new(refraction_index: Float) -> Self {
+        Self {
+            medium: Medium::clear(refraction_index),
+        }
     }
 
-    fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
+    /// Glass whose index of refraction changes with wavelength, which splits
+    /// light into its colors when the camera traces wavelengths.
+    #[must_use]
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        Self {
+            medium: Medium::clear(1.0).with_dispersion(dispersion),
+        }
+    }
+
+    /// Colors the inside, so only `color` is let through after light has
+    /// travelled `distance` in it, as tinted glass does.
+    #[must_use]
+    pub fn with_absorption(mut self, color: Color, distance: Float) -> Self {
+        self.medium = self.medium.with_tint(color, distance);
+        self
+    }
+
+    fn reflectance(cosine: Float, refraction_index: Float) -> Float {
         let r0 = ((1.0 - refraction_index) / (1.0 + refraction_index)).powi(2);
         r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Color, Ray, f64)> {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
         let attenuation = color(1.0, 1.0, 1.0);
-        let refraction_index = tern!(
-            rec.front_face,
-            1.0 / self.refraction_index,
-            self.refraction_index
-        );
+        let wavelength = path.wavelengths.map(|w| w.hero());
+        let surrounding = path.media.surrounding(self.medium, rec.front_face);
+        let outside = surrounding.refraction_index_at(wavelength);
+        let inside = self.medium.refraction_index_at(wavelength);
+        let refraction_index = tern!(rec.front_face, outside / inside, inside / outside);
 
         let unit_direction = r.dir.unit_vector();
 
@@ -43,23 +62,43 @@ impl Material for Dielectric {
         let cannot_refract = refraction_index * sin_theta > 1.0;
 
         let direction = tern!(
-            cannot_refract || Dielectric::reflectance(cos_theta, refraction_index) > rand_f64(),
+            cannot_refract || Dielectric::reflectance(cos_theta, refraction_index) > rand_float(),
             unit_direction.reflect(rec.normal),
             unit_direction.refract(rec.normal, refraction_index)
         );
 
-        let scattered = ray(rec.p, direction, r.time);
-        Some((attenuation, scattered, 0.0))
+        // every other wavelength would have been bent its own way
+        if self.medium.dispersion.is_some() || surrounding.dispersion.is_some() {
+            path.terminate_secondary();
+        }
+
+        Some(ScatterRecord::SkipPdf {
+            attenuation,
+            ray: rec.spawn_ray(direction, r.time),
+        })
+    }
+
+    fn interior(&self) -> Option<Medium> {
+        Some(self.medium)
     }
 }
 
 impl fmt::Display for Dielectric {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "dielectric({})", self.refraction_index)
+        write!(
+            f,
+            "dielectric({}, {})",
+            self.medium.refraction_index, self.medium.absorption
+        )
     }
 }
 
 #[must_use]
-pub fn dielectric(refraction_index: f64) -> Arc<Dielectric> {
+pub fn dielectric(refraction_index: Float) -> Arc<Dielectric> {
     Arc::new(Dielectric::new(refraction_index))
 }
+
+#[must_use]
+pub fn dispersive_dielectric(dispersion: Dispersion) -> Arc<Dielectric> {
+    Arc::new(Dielectric::dispersive(dispersion))
+}
@@ -5,44 +5,76 @@ use std::{
 
 use crate::{
     hittables::HitRecord,
-    primitives::{Color, Point3, Ray, color},
+    primitives::{Blackbody, Color, Float, PathState, Point3, Ray, color},
     textures::{Texture, solid_color},
 };
 
-use super::Material;
+use super::{Material, ScatterRecord};
 
 pub struct DiffuseLight {
     tex: Arc<dyn Texture>,
+    /// The spectrum the texture scales, when it isn't white.
+    blackbody: Option<Blackbody>,
 }
 
 impl DiffuseLight {
     fn new(tex: Arc<dyn Texture>) -> Self {
-        Self { tex }
+        Self {
+            tex,
+            blackbody: None,
+        }
     }
 
     fn new_from_color(emit: Color) -> Self {
+        Self::new(solid_color(emit))
+    }
+
+    /// A light glowing as a black body at `temperature` kelvin would, with
+    /// a luminance of `strength`.
+    fn blackbody(temperature: Float, strength: Float) -> Self {
         Self {
-            tex: solid_color(emit),
+            tex: solid_color(color(strength, strength, strength)),
+            blackbody: Some(Blackbody::new(temperature)),
         }
     }
 }
 
 impl Material for DiffuseLight {
-    fn scatter(&self, _r: &Ray, _rec: &HitRecord) -> Option<(Color, Ray, f64)> {
+    fn scatter(&self, _r: &Ray, _rec: &HitRecord, _path: &mut PathState) -> Option<ScatterRecord> {
         None
     }
 
-    fn emitted(&self, _r: &Ray, rec: &HitRecord, u: f64, v: f64, p: Point3) -> Color {
+    fn emitted(
+        &self,
+        _r: &Ray,
+        rec: &HitRecord,
+        u: Float,
+        v: Float,
+        p: Point3,
+        path: &PathState,
+    ) -> Color {
         if !rec.front_face {
             return color(0.0, 0.0, 0.0);
         }
-        self.tex.value(u, v, p)
+
+        let emit = path.spectrum(self.tex.value(u, v, p));
+        self.blackbody.map_or(emit, |blackbody| {
+            emit * blackbody.spectrum(path.wavelengths)
+        })
     }
 }
 
 impl Display for DiffuseLight {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "diffuse light({})", self.tex)
+        match self.blackbody {
+            Some(blackbody) => write!(
+                f,
+                "diffuse light({}, {}K)",
+                self.tex,
+                blackbody.temperature()
+            ),
+            None => write!(f, "diffuse light({})", self.tex),
+        }
     }
 }
 
@@ -54,3 +86,8 @@ pub fn diffuse_light(tex: Arc<dyn Texture>) -> Arc<DiffuseLight> {
 pub fn diffuse_light_from_color(color: Color) -> Arc<DiffuseLight> {
     Arc::new(DiffuseLight::new_from_color(color))
 }
+
+#[must_use]
+pub fn blackbody_light(temperature: Float, strength: Float) -> Arc<DiffuseLight> {
+    Arc::new(DiffuseLight::blackbody(temperature, strength))
+}
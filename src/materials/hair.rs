@@ -0,0 +1,416 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    hittables::HitRecord,
+    misc::rand_float,
+    primitives::{
+        Color, Float, PathState, Ray, Vec3, color,
+        consts::{LN_2, PI, TAU},
+        vec3,
+    },
+    tern,
+};
+
+use super::{Material, ScatterRecord};
+
+/// How many of the paths through the fiber are modelled separately: `R`,
+/// `TT` and `TRT`. Longer ones are lumped together in a final lobe.
+const P_MAX: usize = 3;
+
+/// The hair scattering model of "A Practical and Controllable Hair and Fur
+/// Model for Production Path Tracing" (Chiang et al.), as described in PBRT.
+/// Meant for `Curve`s, where `v` runs across the fiber. Light is reflected
+/// off the cuticle, and transmitted through the fiber after being reflected
+/// inside it any number of times, absorbed by `sigma_a` along the way.
+pub struct Hair {
+    sigma_a: Color,
+    beta_m: Float,
+    beta_n: Float,
+    alpha: Float,
+    eta: Float,
+    /// Longitudinal variance of each lobe.
+    v: [Float; P_MAX + 1],
+    /// Logistic scale of the azimuthal lobes.
+    s: Float,
+    /// `sin` and `cos` of twice, four times and eight times `alpha`.
+    sin_2k_alpha: [Float; 3],
+    cos_2k_alpha: [Float; 3],
+}
+
+impl Hair {
+    /// `beta_m` and `beta_n` are the longitudinal and azimuthal roughness in
+    /// `[0, 1]`, and `alpha` is how far the cuticle scales tilt the surface,
+    /// in degrees. `eta` is the index of refraction of the fiber, usually
+    /// about 1.55.
+    #[must_use]
+    pub fn new(sigma_a: Color, beta_m: Float, beta_n: Float, alpha: Float, eta: Float) -> Self {
+        let v0 = (0.726 * beta_m + 0.812 * beta_m.powi(2) + 3.7 * beta_m.powi(20)).powi(2);
+        let v = [v0, 0.25 * v0, 4.0 * v0, 4.0 * v0];
+
+        let s =
+            (PI / 8.0).sqrt() * (0.265 * beta_n + 1.194 * beta_n.powi(2) + 5.372 * beta_n.powi(22));
+
+        let mut sin_2k_alpha = [alpha.to_radians().sin(); 3];
+        let mut cos_2k_alpha = [safe_sqrt(1.0 - sin_2k_alpha[0].powi(2)); 3];
+        for i in 1..3 {
+            sin_2k_alpha[i] = 2.0 * cos_2k_alpha[i - 1] * sin_2k_alpha[i - 1];
+            cos_2k_alpha[i] = cos_2k_alpha[i - 1].powi(2) - sin_2k_alpha[i - 1].powi(2);
+        }
+
+        Self {
+            sigma_a,
+            beta_m,
+            beta_n,
+            alpha,
+            eta,
+            v,
+            s,
+            sin_2k_alpha,
+            cos_2k_alpha,
+        }
+    }
+
+    /// Hair colored by the concentrations of the two melanin pigments. Brown
+    /// and black hair has about 1.3 to 8 of `eumelanin`, blond hair less, and
+    /// red hair gets its color from `pheomelanin`.
+    #[must_use]
+    pub fn from_melanin(
+        eumelanin: Float,
+        pheomelanin: Float,
+        beta_m: Float,
+        beta_n: Float,
+    ) -> Self {
+        let eumelanin_sigma_a = color(0.419, 0.697, 1.37);
+        let pheomelanin_sigma_a = color(0.187, 0.4, 1.05);
+        let sigma_a = eumelanin_sigma_a * eumelanin + pheomelanin_sigma_a * pheomelanin;
+        Self::new(sigma_a, beta_m, beta_n, 2.0, 1.55)
+    }
+
+    /// Hair that looks roughly `reflectance` colored after many bounces,
+    /// which is easier to pick than an absorption coefficient.
+    #[must_use]
+    pub fn from_reflectance(reflectance: Color, beta_m: Float, beta_n: Float) -> Self {
+        let scale = 5.969 - 0.215 * beta_n + 2.532 * beta_n.powi(2) - 10.73 * beta_n.powi(3)
+            + 5.574 * beta_n.powi(4)
+            + 0.245 * beta_n.powi(5);
+        let sigma_a = reflectance.map(|c| (c.ln() / scale).powi(2));
+        Self::new(sigma_a, beta_m, beta_n, 2.0, 1.55)
+    }
+
+    /// The direction of `wo` relative to the cuticle scales, which tilt it
+    /// differently for each lobe.
+    fn tilt(&self, p: usize, sin_theta_o: Float, cos_theta_o: Float) -> (Float, Float) {
+        let (sin, cos) = (self.sin_2k_alpha, self.cos_2k_alpha);
+        let (sin_theta, cos_theta) = match p {
+            0 => (
+                sin_theta_o * cos[1] - cos_theta_o * sin[1],
+                cos_theta_o * cos[1] + sin_theta_o * sin[1],
+            ),
+            1 => (
+                sin_theta_o * cos[0] + cos_theta_o * sin[0],
+                cos_theta_o * cos[0] - sin_theta_o * sin[0],
+            ),
+            2 => (
+                sin_theta_o * cos[2] + cos_theta_o * sin[2],
+                cos_theta_o * cos[2] - sin_theta_o * sin[2],
+            ),
+            _ => (sin_theta_o, cos_theta_o),
+        };
+        (sin_theta, cos_theta.abs())
+    }
+
+    /// What's left of light passing through the fiber along each path, and
+    /// the angle it's refracted to inside the fiber.
+    fn attenuation(&self, sin_theta_o: Float, cos_theta_o: Float, h: Float) -> ([Color; 4], Float) {
+        let sin_theta_t = sin_theta_o / self.eta;
+        let cos_theta_t = safe_sqrt(1.0 - sin_theta_t * sin_theta_t);
+
+        // the modified index of refraction for the fiber's cross section
+        let etap = (self.eta * self.eta - sin_theta_o * sin_theta_o).sqrt() / cos_theta_o;
+        let sin_gamma_t = h / etap;
+        let cos_gamma_t = safe_sqrt(1.0 - sin_gamma_t * sin_gamma_t);
+        let gamma_t = sin_gamma_t.clamp(-1.0, 1.0).asin();
+
+        let transmittance = (self.sigma_a * (2.0 * cos_gamma_t / cos_theta_t)).map(|x| (-x).exp());
+
+        let cos_gamma_o = safe_sqrt(1.0 - h * h);
+        let f = fresnel_dielectric(cos_theta_o * cos_gamma_o, self.eta);
+        let white = color(1.0, 1.0, 1.0);
+
+        let r = white * f;
+        let tt = transmittance * (1.0 - f).powi(2);
+        let trt = tt * transmittance * f;
+        let rest = trt * transmittance * f * (white - transmittance * f).map(|x| 1.0 / x);
+
+        ([r, tt, trt, rest], gamma_t)
+    }
+
+    /// The bsdf times the cosine of `wi`, in the local frame of the fiber
+    /// where x runs along it and `(y, z)` across.
+    fn eval(&self, wo: Vec3, wi: Vec3, h: Float) -> Color {
+        let sin_theta_o = wo.x;
+        let cos_theta_o = safe_sqrt(1.0 - sin_theta_o * sin_theta_o);
+        let phi_o = wo.z.atan2(wo.y);
+
+        let sin_theta_i = wi.x;
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i * sin_theta_i);
+        let phi_i = wi.z.atan2(wi.y);
+
+        let gamma_o = h.clamp(-1.0, 1.0).asin();
+        let (ap, gamma_t) = self.attenuation(sin_theta_o, cos_theta_o, h);
+        let phi = phi_i - phi_o;
+
+        let lobes = (0..P_MAX).fold(Color::default(), |sum, p| {
+            let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+            let m = longitudinal(
+                cos_theta_i,
+                cos_theta_op,
+                sin_theta_i,
+                sin_theta_op,
+                self.v[p],
+            );
+            let n = azimuthal(phi, p, self.s, gamma_o, gamma_t);
+            sum + ap[p] * (m * n)
+        });
+
+        let m = longitudinal(
+            cos_theta_i,
+            cos_theta_o,
+            sin_theta_i,
+            sin_theta_o,
+            self.v[P_MAX],
+        );
+        lobes + ap[P_MAX] * (m / TAU)
+    }
+
+    /// How likely each path through the fiber is to be sampled, following
+    /// how much light it carries.
+    fn lobe_pdfs(&self, sin_theta_o: Float, cos_theta_o: Float, h: Float) -> [Float; P_MAX + 1] {
+        let (ap, _) = self.attenuation(sin_theta_o, cos_theta_o, h);
+        let luminance = ap.map(|c| 0.2126 * c.r + 0.7152 * c.g + 0.0722 * c.b);
+        let total: Float = luminance.iter().sum();
+        luminance.map(|l| tern!(total > 0.0, l / total, 0.25))
+    }
+
+    /// Samples a direction for `wo` in the local frame of the fiber, returning
+    /// it with its pdf.
+    fn sample(&self, wo: Vec3, h: Float) -> (Vec3, Float) {
+        let sin_theta_o = wo.x;
+        let cos_theta_o = safe_sqrt(1.0 - sin_theta_o * sin_theta_o);
+        let phi_o = wo.z.atan2(wo.y);
+        let gamma_o = h.clamp(-1.0, 1.0).asin();
+
+        // pick a path through the fiber
+        let lobe_pdfs = self.lobe_pdfs(sin_theta_o, cos_theta_o, h);
+        let mut choice = rand_float();
+        let mut p = 0;
+        while p < P_MAX && choice >= lobe_pdfs[p] {
+            choice -= lobe_pdfs[p];
+            p += 1;
+        }
+
+        // sample the longitudinal lobe around the tilted mirror direction
+        let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+        let u = rand_float().max(1e-5);
+        let v = self.v[p];
+        let cos_theta = 1.0 + v * (u + (1.0 - u) * (-2.0 / v).exp()).ln();
+        let sin_theta = safe_sqrt(1.0 - cos_theta * cos_theta);
+        let cos_phi = (TAU * rand_float()).cos();
+        let sin_theta_i = -cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op;
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i * sin_theta_i);
+
+        // then the azimuthal lobe around the exit of the chosen path
+        let (_, gamma_t) = self.attenuation(sin_theta_o, cos_theta_o, h);
+        let dphi = if p < P_MAX {
+            exit_azimuth(p, gamma_o, gamma_t) + sample_trimmed_logistic(rand_float(), self.s)
+        } else {
+            TAU * rand_float()
+        };
+
+        let phi_i = phi_o + dphi;
+        let wi = vec3(
+            sin_theta_i,
+            cos_theta_i * phi_i.cos(),
+            cos_theta_i * phi_i.sin(),
+        );
+
+        // any path could have produced wi
+        let lobes = (0..P_MAX).fold(0.0, |sum, p| {
+            let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+            let m = longitudinal(
+                cos_theta_i,
+                cos_theta_op,
+                sin_theta_i,
+                sin_theta_op,
+                self.v[p],
+            );
+            sum + m * lobe_pdfs[p] * azimuthal(dphi, p, self.s, gamma_o, gamma_t)
+        });
+        let m = longitudinal(
+            cos_theta_i,
+            cos_theta_o,
+            sin_theta_i,
+            sin_theta_o,
+            self.v[P_MAX],
+        );
+        let pdf = lobes + m * lobe_pdfs[P_MAX] / TAU;
+
+        (wi, pdf)
+    }
+}
+
+impl Material for Hair {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        // x along the fiber, y across it following v, z out of it
+        let (x, y) = (rec.tangent, rec.bitangent);
+        let z = x.cross(y);
+        let to_local = |w: Vec3| vec3(w.dot(x), w.dot(y), w.dot(z));
+
+        let h = -1.0 + 2.0 * rec.v;
+        let wo = to_local(-r.dir.unit_vector());
+        let (wi, pdf) = self.sample(wo, h);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        let dir = x * wi.x + y * wi.y + z * wi.z;
+        Some(ScatterRecord::SkipPdf {
+            attenuation: path.spectrum(self.eval(wo, wi, h) * (1.0 / pdf)),
+            ray: rec.spawn_ray(dir, r.time),
+        })
+    }
+}
+
+impl fmt::Display for Hair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hair({:?}, {}, {}, {}, {})",
+            self.sigma_a.tuple(),
+            self.beta_m,
+            self.beta_n,
+            self.alpha,
+            self.eta
+        )
+    }
+}
+
+#[must_use]
+pub fn hair(sigma_a: Color, beta_m: Float, beta_n: Float) -> Arc<Hair> {
+    Arc::new(Hair::new(sigma_a, beta_m, beta_n, 2.0, 1.55))
+}
+
+#[must_use]
+pub fn hair_from_melanin(
+    eumelanin: Float,
+    pheomelanin: Float,
+    beta_m: Float,
+    beta_n: Float,
+) -> Arc<Hair> {
+    Arc::new(Hair::from_melanin(eumelanin, pheomelanin, beta_m, beta_n))
+}
+
+#[must_use]
+pub fn hair_from_reflectance(reflectance: Color, beta_m: Float, beta_n: Float) -> Arc<Hair> {
+    Arc::new(Hair::from_reflectance(reflectance, beta_m, beta_n))
+}
+
+fn safe_sqrt(x: Float) -> Float {
+    x.max(0.0).sqrt()
+}
+
+/// Fresnel reflectance of a dielectric for light arriving from outside.
+fn fresnel_dielectric(cos_theta_i: Float, eta: Float) -> Float {
+    let cos_theta_i = cos_theta_i.clamp(-1.0, 1.0);
+    let (eta, cos_theta_i) = tern!(
+        cos_theta_i < 0.0,
+        (1.0 / eta, -cos_theta_i),
+        (eta, cos_theta_i)
+    );
+
+    let sin_theta_t = (1.0 - cos_theta_i * cos_theta_i).max(0.0).sqrt() / eta;
+    if sin_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = safe_sqrt(1.0 - sin_theta_t * sin_theta_t);
+
+    let parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (parallel * parallel).midpoint(perpendicular * perpendicular)
+}
+
+/// The modified Bessel function of the first kind, `I0`.
+fn bessel_i0(x: Float) -> Float {
+    let mut value = 0.0;
+    let mut x_2i = 1.0;
+    let mut factorial = 1.0;
+    let mut four_i = 1.0;
+    for i in 0..10_u8 {
+        if i > 1 {
+            factorial *= Float::from(i);
+        }
+        value += x_2i / (four_i * factorial * factorial);
+        x_2i *= x * x;
+        four_i *= 4.0;
+    }
+    value
+}
+
+fn log_bessel_i0(x: Float) -> Float {
+    if x > 12.0 {
+        x + 0.5 * (-(TAU.ln()) + (1.0 / x).ln() + 1.0 / (8.0 * x))
+    } else {
+        bessel_i0(x).ln()
+    }
+}
+
+/// The longitudinal scattering function `Mp`.
+fn longitudinal(
+    cos_theta_i: Float,
+    cos_theta_o: Float,
+    sin_theta_i: Float,
+    sin_theta_o: Float,
+    v: Float,
+) -> Float {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+
+    // the direct form overflows for low roughness
+    if v <= 0.1 {
+        (log_bessel_i0(a) - b - 1.0 / v + LN_2 + (1.0 / (2.0 * v)).ln()).exp()
+    } else {
+        ((-b).exp() * bessel_i0(a)) / ((1.0 / v).sinh() * 2.0 * v)
+    }
+}
+
+/// The azimuth a ray leaves the fiber at after taking path `p` through it.
+fn exit_azimuth(p: usize, gamma_o: Float, gamma_t: Float) -> Float {
+    let p = p as Float;
+    2.0 * p * gamma_t - 2.0 * gamma_o + p * PI
+}
+
+fn logistic(x: Float, s: Float) -> Float {
+    let x = x.abs();
+    (-x / s).exp() / (s * (1.0 + (-x / s).exp()).powi(2))
+}
+
+fn logistic_cdf(x: Float, s: Float) -> Float {
+    1.0 / (1.0 + (-x / s).exp())
+}
+
+/// A logistic distribution cut down to `[-pi, pi]`.
+fn trimmed_logistic(x: Float, s: Float) -> Float {
+    logistic(x, s) / (logistic_cdf(PI, s) - logistic_cdf(-PI, s))
+}
+
+fn sample_trimmed_logistic(u: Float, s: Float) -> Float {
+    let k = logistic_cdf(PI, s) - logistic_cdf(-PI, s);
+    let x = -s * (1.0 / (u * k + logistic_cdf(-PI, s)) - 1.0).ln();
+    x.clamp(-PI, PI)
+}
+
+/// The azimuthal scattering function `Np`.
+fn azimuthal(phi: Float, p: usize, s: Float, gamma_o: Float, gamma_t: Float) -> Float {
+    let dphi = (phi - exit_azimuth(p, gamma_o, gamma_t) + PI).rem_euclid(TAU) - PI;
+    trimmed_logistic(dphi, s)
+}
@@ -1,16 +1,16 @@
 use std::{
-    f64::consts::PI,
     fmt::{self, Display},
     sync::Arc,
 };
 
 use crate::{
     hittables::HitRecord,
-    primitives::{Color, Ray, Vec3, ray},
+    pdfs::SpherePdf,
+    primitives::{Color, Float, PathState, Ray, consts::PI},
     textures::{Texture, solid_color},
 };
 
-use super::Material;
+use super::{Material, ScatterRecord};
 
 pub struct Isotropic {
     tex: Arc<dyn Texture>,
@@ -30,13 +30,20 @@ impl Isotropic {
 }
 
 impl Material for Isotropic {
-    fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Color, Ray, f64)> {
-        let scattered = ray(rec.p, Vec3::random_unit_vector(), r.time);
-        let attenuation = self.tex.value(rec.u, rec.v, rec.p);
-        Some((attenuation, scattered, 1.0 / (4.0 * PI)))
+    fn scatter(&self, _r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Pdf {
+            attenuation: path.spectrum(self.tex.value(rec.u, rec.v, rec.p)),
+            pdf: Box::new(SpherePdf),
+        })
     }
 
-    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+    fn scattering_pdf(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _scattered: &Ray,
+        _path: &PathState,
+    ) -> Float {
         1.0 / (4.0 * PI)
     }
 }
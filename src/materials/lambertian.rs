@@ -1,10 +1,14 @@
-use std::{f64::consts::PI, fmt, sync::Arc};
+use std::{fmt, sync::Arc};
 
 use crate::{
-    hittables::HitRecord, misc::random_cosine_direction, primitives::{ray, Color, Onb, Ray, Vec3}, tern, textures::{SolidColor, Texture}
+    hittables::HitRecord,
+    pdfs::CosinePdf,
+    primitives::{Color, Float, PathState, Ray, consts::PI},
+    tern,
+    textures::{SolidColor, Texture},
 };
 
-use super::Material;
+use super::{Material, ScatterRecord};
 
 #[derive(Clone)]
 pub struct Lambertian {
@@ -24,17 +28,24 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Color, Ray, f64)> {
-        let uvw = Onb::new(rec.normal);
-        let scatter_direction = uvw.transform(random_cosine_direction());
-
-        let scattered = ray(rec.p, scatter_direction.unit_vector(), r.time);
-        let attenuation = self.tex.value(rec.u, rec.v, rec.p);
-        let pdf = uvw.w().dot(scattered.dir) / PI;
-        Some((attenuation, scattered, pdf))
+    fn scatter(&self, _r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Pdf {
+            attenuation: path.spectrum(self.tex.value(rec.u, rec.v, rec.p)),
+            pdf: Box::new(CosinePdf::new(rec.shading_frame())),
+        })
     }
 
-    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+    fn scattering_pdf(
+        &self,
+        _r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        _path: &PathState,
+    ) -> Float {
+        if !rec.is_reflection(scattered.dir) {
+            return 0.0;
+        }
+
         let cos_theta = rec.normal.dot(scattered.dir.unit_vector());
         tern!(cos_theta < 0.0, 0.0, cos_theta / PI)
     }
@@ -2,22 +2,86 @@ use std::fmt;
 
 use crate::{
     hittables::HitRecord,
-    primitives::{Color, Point3, Ray, color},
+    pdfs::Pdf,
+    primitives::{Color, Float, Medium, PathState, Point3, Ray, Vec3, color},
 };
 
+/// How a material scatters a ray that hit it. Like every color a material
+/// gives for a path, the attenuation is at each of the path's wavelengths
+/// when it traces them, which `PathState::spectrum` gives for RGB colors.
+pub enum ScatterRecord {
+    /// Scatters in directions following `pdf`. The integrator mixes it with
+    /// sampling the lights and weights each direction by `scattering_pdf`.
+    Pdf {
+        attenuation: Color,
+        pdf: Box<dyn Pdf>,
+    },
+    /// Scatters along `ray`, which the material chose itself, as a mirror
+    /// does. `attenuation` already accounts for the cosine and the pdf of
+    /// choosing `ray`.
+    SkipPdf { attenuation: Color, ray: Ray },
+}
+
 pub trait Material: Send + Sync + fmt::Display {
-    /// Describes how a ray should be scattered given an input ray and the hit record of that ray
-    /// Returns an option of the color attenuation and the output ray. None means the ray was absorbed
-    /// Defaults to None
-    fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Color, Ray, f64)>;
+    /// Describes how a ray should be scattered given an input ray and the
+    /// hit record of that ray. None means the ray was absorbed. Materials
+    /// that split up wavelengths stop `path` tracing all but the hero.
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord>;
 
-    fn emitted(&self, r: &Ray, rec: &HitRecord, u: f64, v: f64, p: Point3) -> Color {
-        let _ = (r, rec, u, v, p);
+    /// The light given off towards `r`. When `path` traces wavelengths it's
+    /// the radiance at each of them, which `PathState::spectrum` gives for
+    /// RGB colors.
+    fn emitted(
+        &self,
+        r: &Ray,
+        rec: &HitRecord,
+        u: Float,
+        v: Float,
+        p: Point3,
+        path: &PathState,
+    ) -> Color {
+        let _ = (r, rec, u, v, p, path);
         color(0.0, 0.0, 0.0)
     }
 
-    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
-        let _ = (r_in, rec, scattered);
+    /// Lets the material bend the shading normal at a hit before it's shaded,
+    /// as normal and bump maps do. `None` leaves the normal alone.
+    fn perturbed_normal(&self, rec: &HitRecord) -> Option<Vec3> {
+        let _ = rec;
+        None
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Float {
+        let _ = (r_in, rec, scattered, path);
         0.0
     }
+
+    /// How much of the light arriving along `scattered` leaves towards
+    /// `r_in`, the BSDF times the cosine, which weights the attenuation of a
+    /// `ScatterRecord::Pdf`, at each of `path`'s wavelengths when it traces
+    /// them. Defaults to `scattering_pdf` in every channel, which is all a
+    /// material needs when its color is in the attenuation.
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        let pdf = self.scattering_pdf(r_in, rec, scattered, path);
+        color(pdf, pdf, pdf)
+    }
+
+    /// What fills the inside of surfaces made of the material, which rays
+    /// passing through them enter and leave. `None` for opaque materials and
+    /// ones that are only a thin wall.
+    fn interior(&self) -> Option<Medium> {
+        None
+    }
 }
@@ -2,33 +2,38 @@ use std::{fmt, sync::Arc};
 
 use crate::{
     hittables::HitRecord,
-    primitives::{Color, Ray, Vec3, ray},
+    primitives::{Color, Float, PathState, Ray, Vec3},
 };
 
-use super::Material;
+use super::{Material, ScatterRecord};
 
 #[derive(Clone)]
 pub struct Metal {
     albedo: Color,
-    fuzz: f64,
+    fuzz: Float,
 }
 
 impl Metal {
     #[must_use]
-    pub fn new(albedo: Color, fuzz: f64) -> Self {
+    pub fn new(albedo: Color, fuzz: Float) -> Self {
         Self { albedo, fuzz }
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Color, Ray, f64)> {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
         let reflected = r.dir.reflect(rec.normal);
         let reflected_fuzzed = reflected.unit_vector() + (Vec3::random_unit_vector() * self.fuzz);
-        let scattered = ray(rec.p, reflected_fuzzed, r.time);
-        let attenuation = self.albedo;
+        let scattered = rec.spawn_ray(reflected_fuzzed, r.time);
+        let attenuation = path.spectrum(self.albedo);
 
         // if we scatter below the surface, just absorb the ray
-        (scattered.dir.dot(rec.normal) > 0.0).then_some((attenuation, scattered, 0.0))
+        (scattered.dir.dot(rec.normal) > 0.0 && rec.is_reflection(scattered.dir)).then_some(
+            ScatterRecord::SkipPdf {
+                attenuation,
+                ray: scattered,
+            },
+        )
     }
 }
 
@@ -39,6 +44,6 @@ impl fmt::Display for Metal {
 }
 
 #[must_use]
-pub fn metal(albedo: Color, fuzz: f64) -> Arc<Metal> {
+pub fn metal(albedo: Color, fuzz: Float) -> Arc<Metal> {
     Arc::new(Metal::new(albedo, fuzz))
 }
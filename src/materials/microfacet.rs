@@ -0,0 +1,323 @@
+use crate::{
+    hittables::HitRecord,
+    misc::rand_float,
+    pdfs::Pdf,
+    primitives::{Color, Float, Onb, Ray, Vec3, consts::PI, vec3},
+    tern,
+};
+
+use super::ScatterRecord;
+
+/// Below this the distribution is treated as a perfectly smooth surface,
+/// whose normals can't be sampled without running out of precision.
+const SMOOTH_ALPHA: Float = 1e-3;
+
+/// The Trowbridge-Reitz (GGX) distribution of microfacet normals, in a local
+/// frame where the surface normal is `z`. `alpha_x` and `alpha_y` are how
+/// rough the surface is along `x` and `y`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct TrowbridgeReitz {
+    alpha_x: Float,
+    alpha_y: Float,
+}
+
+impl TrowbridgeReitz {
+    /// Maps perceptual roughness in `[0, 1]` along each tangent to the
+    /// distribution, squaring it so roughness looks about linear. It's kept
+    /// just above zero, so a surface smooth along only one tangent still has
+    /// a density everywhere.
+    pub fn new(roughness_x: Float, roughness_y: Float) -> Self {
+        let alpha = |roughness: Float| roughness.clamp(0.0, 1.0).powi(2).max(1e-4);
+        Self {
+            alpha_x: alpha(roughness_x),
+            alpha_y: alpha(roughness_y),
+        }
+    }
+
+    pub fn is_smooth(self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+
+    /// The density of microfacets facing `wm`.
+    pub fn d(self, wm: Vec3) -> Float {
+        let (ax, ay) = (self.alpha_x, self.alpha_y);
+        let e = (wm.x / ax).powi(2) + (wm.y / ay).powi(2) + wm.z.powi(2);
+        1.0 / (PI * ax * ay * e * e)
+    }
+
+    /// Smith's auxiliary function, giving how much of the surface seen from
+    /// `w` is hidden by other microfacets.
+    fn lambda(self, w: Vec3) -> Float {
+        if w.z == 0.0 {
+            return Float::INFINITY;
+        }
+
+        let alpha2_tan2 =
+            ((self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2)) / w.z.powi(2);
+        ((1.0 + alpha2_tan2).sqrt() - 1.0) * 0.5
+    }
+
+    /// The fraction of microfacets seen from `w` that aren't masked.
+    pub fn g1(self, w: Vec3) -> Float {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// The fraction of microfacets seen from both `wo` and `wi`.
+    pub fn g(self, wo: Vec3, wi: Vec3) -> Float {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// The density of microfacets facing `wm` that are visible from `w`,
+    /// which is also the pdf of `sample_wm`.
+    pub fn pdf(self, w: Vec3, wm: Vec3) -> Float {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z.abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    /// Samples a microfacet normal visible from `w` ("Sampling Visible GGX
+    /// Normals with Spherical Caps", as described in PBRT).
+    pub fn sample_wm(self, w: Vec3) -> Vec3 {
+        // stretch w so the distribution becomes a hemisphere
+        let mut wh = vec3(self.alpha_x * w.x, self.alpha_y * w.y, w.z).unit_vector();
+        if wh.z < 0.0 {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z < 0.99999 {
+            vec3(0.0, 0.0, 1.0).cross(wh).unit_vector()
+        } else {
+            vec3(1.0, 0.0, 0.0)
+        };
+        let t2 = wh.cross(t1);
+
+        // a point on the disk, squashed onto the part of it that's visible
+        let r = rand_float().sqrt();
+        let phi = 2.0 * PI * rand_float();
+        let (px, py) = (r * phi.cos(), r * phi.sin());
+        let h = (1.0 - px * px).sqrt();
+        let s = (1.0 + wh.z) * 0.5;
+        let py = (1.0 - s) * h + s * py;
+        let pz = (1.0 - px * px - py * py).max(0.0).sqrt();
+
+        let nh = t1 * px + t2 * py + wh * pz;
+        vec3(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).unit_vector()
+    }
+}
+
+/// Fresnel reflectance of a conductor with complex index of refraction
+/// `eta + i k`, per channel, for light arriving at `cos_theta` to the normal.
+pub(super) fn fresnel_conductor(cos_theta: Float, eta: Color, k: Color) -> Color {
+    let channel = |eta: Float, k: Float| {
+        let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+        let sin2 = 1.0 - cos2;
+        let (eta2, k2) = (eta * eta, k * k);
+
+        let t0 = eta2 - k2 - sin2;
+        let a2_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2_b2 + cos2;
+        let a = (0.5 * (a2_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_theta * a;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+
+        0.5 * (rp + rs)
+    };
+
+    Color::new(
+        channel(eta.r, k.r),
+        channel(eta.g, k.g),
+        channel(eta.b, k.b),
+    )
+}
+
+/// Samples directions reflected off the microfacets visible from `wo`,
+/// which is given in the local `frame`.
+pub(super) struct MicrofacetReflectionPdf {
+    frame: Onb,
+    wo: Vec3,
+    distribution: TrowbridgeReitz,
+}
+
+impl MicrofacetReflectionPdf {
+    pub fn new(frame: Onb, wo: Vec3, distribution: TrowbridgeReitz) -> Self {
+        Self {
+            frame,
+            wo,
+            distribution,
+        }
+    }
+}
+
+impl Pdf for MicrofacetReflectionPdf {
+    fn value(&self, dir: Vec3) -> Float {
+        let wi = self.frame.to_local(dir.unit_vector());
+        reflection_pdf(self.distribution, self.wo, wi)
+    }
+
+    fn generate(&self) -> Vec3 {
+        let wm = self.distribution.sample_wm(self.wo);
+        let wi = (-self.wo).reflect(wm);
+        tern!(wi.z > 0.0, self.frame.transform(wi), Vec3::default())
+    }
+}
+
+/// The pdf of sampling `wi` by reflecting `wo` off a visible microfacet.
+pub(super) fn reflection_pdf(distribution: TrowbridgeReitz, wo: Vec3, wi: Vec3) -> Float {
+    if wo.z * wi.z <= 0.0 {
+        return 0.0;
+    }
+
+    let wm = (wo + wi).unit_vector();
+    distribution.pdf(wo, wm) / (4.0 * wo.dot(wm).abs())
+}
+
+/// Reflects `r` off the microfacets of a dielectric surface, which was
+/// picked over going through it with a chance of `chance`. `reflectance`
+/// gives the light reflected off a microfacet at a cosine to `r`.
+pub(super) fn scatter_reflection(
+    distribution: TrowbridgeReitz,
+    r: &Ray,
+    rec: &HitRecord,
+    chance: Float,
+    reflectance: impl Fn(Float) -> Color,
+) -> Option<ScatterRecord> {
+    let frame = rec.shading_frame();
+    let wo = frame.to_local(-r.dir.unit_vector());
+    let (dir, attenuation) = if distribution.is_smooth() {
+        (r.dir.reflect(rec.normal), reflectance(wo.z) / chance)
+    } else {
+        let wm = distribution.sample_wm(wo);
+        let wi = (-wo).reflect(wm);
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        let masking = distribution.g(wo, wi) / distribution.g1(wo);
+        (
+            frame.transform(wi),
+            reflectance(wo.dot(wm)) * masking / chance,
+        )
+    };
+
+    rec.is_reflection(dir).then(|| ScatterRecord::SkipPdf {
+        attenuation,
+        ray: rec.spawn_ray(dir, r.time),
+    })
+}
+
+/// Fresnel reflectance of a dielectric for light arriving at `cos_theta` to
+/// the normal, where `eta` is the index of refraction past the surface over
+/// the one in front of it.
+pub(super) fn fresnel_dielectric(cos_theta: Float, eta: Float) -> Float {
+    let cos_i = cos_theta.clamp(-1.0, 1.0);
+    let (cos_i, eta) = tern!(cos_i < 0.0, (-cos_i, 1.0 / eta), (cos_i, eta));
+
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) * 0.5
+}
+
+/// Refracts `w`, which points away from the surface on the side of `n`,
+/// through it. `None` means it's totally internally reflected instead.
+pub(super) fn refract(w: Vec3, n: Vec3, eta: Float) -> Option<Vec3> {
+    let cos_i = n.dot(w);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-w / eta + n * (cos_i / eta - cos_t))
+}
+
+/// The microfacet normal that reflects or refracts `wo` into `wi`, facing
+/// the side of `wo`. `None` when no microfacet facing both could.
+pub(super) fn half_vector(wo: Vec3, wi: Vec3, eta: Float) -> Option<Vec3> {
+    let eta = tern!(wi.z > 0.0, 1.0, eta);
+    let wm = wi * eta + wo;
+    if wi.z == 0.0 || wm.length_squared() == 0.0 {
+        return None;
+    }
+
+    let wm = wm.unit_vector();
+    let wm = tern!(wm.z < 0.0, -wm, wm);
+    (wm.dot(wi) * wi.z > 0.0 && wm.dot(wo) * wo.z > 0.0).then_some(wm)
+}
+
+/// Samples directions reflected off or refracted through the microfacets
+/// visible from `wo`, in proportion to the Fresnel reflectance of each.
+pub(super) struct MicrofacetDielectricPdf {
+    frame: Onb,
+    wo: Vec3,
+    eta: Float,
+    distribution: TrowbridgeReitz,
+}
+
+impl MicrofacetDielectricPdf {
+    pub fn new(frame: Onb, wo: Vec3, eta: Float, distribution: TrowbridgeReitz) -> Self {
+        Self {
+            frame,
+            wo,
+            eta,
+            distribution,
+        }
+    }
+}
+
+impl Pdf for MicrofacetDielectricPdf {
+    fn value(&self, dir: Vec3) -> Float {
+        let wi = self.frame.to_local(dir.unit_vector());
+        dielectric_pdf(self.distribution, self.eta, self.wo, wi)
+    }
+
+    fn generate(&self) -> Vec3 {
+        let wm = self.distribution.sample_wm(self.wo);
+        let reflected = (-self.wo).reflect(wm);
+
+        let (wi, is_reflection) = if rand_float() < fresnel_dielectric(self.wo.dot(wm), self.eta) {
+            (reflected, true)
+        } else {
+            refract(self.wo, wm, self.eta).map_or((reflected, true), |wi| (wi, false))
+        };
+
+        // the microfacet can send it to the wrong side of the surface
+        tern!(
+            (wi.z > 0.0) == is_reflection,
+            self.frame.transform(wi),
+            Vec3::default()
+        )
+    }
+}
+
+/// The pdf of sampling `wi` by reflecting `wo` off, or refracting it
+/// through, a visible microfacet.
+pub(super) fn dielectric_pdf(
+    distribution: TrowbridgeReitz,
+    eta: Float,
+    wo: Vec3,
+    wi: Vec3,
+) -> Float {
+    let Some(wm) = half_vector(wo, wi, eta) else {
+        return 0.0;
+    };
+
+    let reflectance = fresnel_dielectric(wo.dot(wm), eta);
+    if wi.z > 0.0 {
+        distribution.pdf(wo, wm) / (4.0 * wo.dot(wm).abs()) * reflectance
+    } else {
+        let denom = (wi.dot(wm) + wo.dot(wm) / eta).powi(2);
+        let dwm_dwi = wi.dot(wm).abs() / denom;
+        distribution.pdf(wo, wm) * dwm_dwi * (1.0 - reflectance)
+    }
+}
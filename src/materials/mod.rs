@@ -1,13 +1,36 @@
+mod bump_mapped;
+mod coated;
+mod conductor;
 mod dielectric;
 mod diffuse_light;
+mod hair;
 mod isotropic;
 mod lambertian;
 mod material;
 mod metal;
+mod microfacet;
+mod normal_mapped;
+mod oren_nayar;
+mod principled;
+mod rough_dielectric;
+mod subsurface;
+mod thin_dielectric;
+mod thin_film;
 
+pub use bump_mapped::*;
+pub use coated::*;
+pub use conductor::*;
 pub use dielectric::*;
 pub use diffuse_light::*;
+pub use hair::*;
 pub use isotropic::*;
 pub use lambertian::*;
 pub use material::*;
 pub use metal::*;
+pub use normal_mapped::*;
+pub use oren_nayar::*;
+pub use principled::*;
+pub use rough_dielectric::*;
+pub use subsurface::*;
+pub use thin_dielectric::*;
+pub use thin_film::*;
@@ -0,0 +1,89 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use crate::{
+    hittables::HitRecord,
+    primitives::{Color, Float, Medium, PathState, Point3, Ray, Vec3, vec3},
+    tern,
+    textures::Texture,
+};
+
+use super::{Material, ScatterRecord};
+
+/// Bends the shading normal of another material with a tangent-space normal
+/// map. Each texel encodes a normal remapped from [-1, 1] to [0, 1], with
+/// red along the tangent, green along the bitangent and blue along the
+/// surface normal.
+pub struct NormalMapped {
+    material: Arc<dyn Material>,
+    map: Arc<dyn Texture>,
+}
+
+impl NormalMapped {
+    pub fn new(material: Arc<dyn Material>, map: Arc<dyn Texture>) -> Self {
+        Self { material, map }
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        self.material.scatter(r, rec, path)
+    }
+
+    fn emitted(
+        &self,
+        r: &Ray,
+        rec: &HitRecord,
+        u: Float,
+        v: Float,
+        p: Point3,
+        path: &PathState,
+    ) -> Color {
+        self.material.emitted(r, rec, u, v, p, path)
+    }
+
+    fn perturbed_normal(&self, rec: &HitRecord) -> Option<Vec3> {
+        let texel = self.map.value(rec.u, rec.v, rec.p);
+        let local = vec3(texel.r, texel.g, texel.b) * 2.0 - vec3(1.0, 1.0, 1.0);
+
+        let normal = rec.shading_frame().transform(local);
+
+        tern!(normal.is_near_zero(), None, Some(normal.unit_vector()))
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Float {
+        self.material.scattering_pdf(r_in, rec, scattered, path)
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        self.material.scattering_color(r_in, rec, scattered, path)
+    }
+
+    fn interior(&self) -> Option<Medium> {
+        self.material.interior()
+    }
+}
+
+impl Display for NormalMapped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "normal_mapped({}, {})", self.material, self.map)
+    }
+}
+
+pub fn normal_mapped(material: Arc<dyn Material>, map: Arc<dyn Texture>) -> Arc<NormalMapped> {
+    Arc::new(NormalMapped::new(material, map))
+}
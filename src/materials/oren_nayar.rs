@@ -0,0 +1,220 @@
+use std::{
+    array, fmt,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    hittables::HitRecord,
+    pdfs::CosinePdf,
+    primitives::{Color, Float, PathState, Ray, Vec3, color, consts::PI},
+    textures::{SolidColor, Texture},
+};
+
+use super::{Material, ScatterRecord};
+
+/// A rough diffuse surface, whose microfacets shadow and light each other
+/// so it looks flatter than a `Lambertian` and brightens towards the light,
+/// as clay, plaster and cloth do. Uses the improved model from "Improvement
+/// of Oren-Nayar Reflectance Model" (Fujii 2012).
+///
+/// It can have a sheen, a soft glow at grazing angles from fibers standing
+/// up off the surface, as on velvet and other fabrics. Both are sampled by
+/// the cosine, as the sheen is too broad to be worth sampling on its own.
+#[derive(Clone)]
+pub struct OrenNayar {
+    tex: Arc<dyn Texture>,
+    roughness: Float,
+    sheen: Option<Sheen>,
+}
+
+impl OrenNayar {
+    /// `roughness` is in `[0, 1]`, where zero is `Lambertian`.
+    #[must_use]
+    pub fn new(tex: Arc<dyn Texture>, roughness: Float) -> Self {
+        Self {
+            tex,
+            roughness: roughness.clamp(0.0, 1.0),
+            sheen: None,
+        }
+    }
+
+    #[must_use]
+    pub fn from_color(albedo: Color, roughness: Float) -> Self {
+        Self::new(Arc::new(SolidColor::new(albedo)), roughness)
+    }
+
+    /// Adds a sheen of `color`, where `roughness` in `[0, 1]` is how far
+    /// from grazing angles it spreads.
+    #[must_use]
+    pub fn with_sheen(mut self, color: Color, roughness: Float) -> Self {
+        self.sheen = Some(Sheen::new(color, roughness));
+        self
+    }
+
+    /// The diffuse BRDF over the albedo, times pi.
+    fn diffuse(&self, wo: Vec3, wi: Vec3) -> Float {
+        let a = 1.0 / (1.0 + (0.5 - 2.0 / (3.0 * PI)) * self.roughness);
+        let b = self.roughness * a;
+
+        let s = wo.dot(wi) - wo.z * wi.z;
+        let t = if s > 0.0 { wo.z.max(wi.z) } else { 1.0 };
+        a + b * s / t
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, _r: &Ray, rec: &HitRecord, _path: &mut PathState) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Pdf {
+            attenuation: color(1.0, 1.0, 1.0),
+            pdf: Box::new(CosinePdf::new(rec.shading_frame())),
+        })
+    }
+
+    fn scattering_pdf(
+        &self,
+        _r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        _path: &PathState,
+    ) -> Float {
+        if !rec.is_reflection(scattered.dir) {
+            return 0.0;
+        }
+
+        let cos_theta = rec.normal.dot(scattered.dir.unit_vector());
+        (cos_theta / PI).max(0.0)
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r_in.dir.unit_vector());
+        let wi = frame.to_local(scattered.dir.unit_vector());
+        if !rec.is_reflection(scattered.dir) || wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::default();
+        }
+
+        let albedo = self.tex.value(rec.u, rec.v, rec.p);
+        let diffuse = albedo * (self.diffuse(wo, wi) * wi.z / PI);
+        path.spectrum(match &self.sheen {
+            Some(sheen) => diffuse * sheen.remaining(wo, wi) + sheen.eval(wo, wi),
+            None => diffuse,
+        })
+    }
+}
+
+impl fmt::Display for OrenNayar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.sheen {
+            Some(sheen) => write!(
+                f,
+                "oren_nayar({}, {}, sheen({}, {}))",
+                self.tex, self.roughness, sheen.color, sheen.roughness
+            ),
+            None => write!(f, "oren_nayar({}, {})", self.tex, self.roughness),
+        }
+    }
+}
+
+/// A sheen following the "Charlie" distribution of fibers from "Production
+/// Friendly Microfacet Sheen BRDF" (Estevez and Kulla 2017), with the
+/// visibility term Ashikhmin used for velvet.
+#[derive(Debug, Clone, Copy)]
+struct Sheen {
+    color: Color,
+    roughness: Float,
+}
+
+impl Sheen {
+    fn new(color: Color, roughness: Float) -> Self {
+        // below this the fibers all lie flat and the lobe can't be sampled
+        Self {
+            color,
+            roughness: roughness.clamp(0.07, 1.0),
+        }
+    }
+
+    /// The BRDF times the cosine of `wi`.
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        self.color * sheen_reflectance(self.roughness, wo, wi)
+    }
+
+    /// How much light is left for the surface below, which the sheen hides
+    /// as much as it reflects when looking from either direction.
+    fn remaining(&self, wo: Vec3, wi: Vec3) -> Float {
+        let strength = self.color.r.max(self.color.g).max(self.color.b);
+        let albedo = sheen_albedo(wo.z, self.roughness).max(sheen_albedo(wi.z, self.roughness));
+        (1.0 - strength * albedo).max(0.0)
+    }
+}
+
+/// A white sheen's BRDF times the cosine of `wi`.
+fn sheen_reflectance(roughness: Float, wo: Vec3, wi: Vec3) -> Float {
+    let alpha = roughness * roughness;
+    let wh = (wo + wi).unit_vector();
+    let sin_theta = (1.0 - wh.z * wh.z).max(0.0).sqrt();
+    let d = (2.0 + 1.0 / alpha) * sin_theta.powf(1.0 / alpha) / (2.0 * PI);
+    let visibility = 1.0 / (4.0 * (wi.z + wo.z - wi.z * wo.z));
+    d * visibility * wi.z
+}
+
+/// How many steps the sheen albedo table has along each axis.
+const ALBEDO_RES: usize = 32;
+
+/// The fraction of light a white sheen reflects when looking from `cos_theta`
+/// to the normal, interpolated from a table integrated the first time it's
+/// needed.
+fn sheen_albedo(cos_theta: Float, roughness: Float) -> Float {
+    static TABLE: OnceLock<[[Float; ALBEDO_RES]; ALBEDO_RES]> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        array::from_fn(|i| {
+            let roughness = (i as Float / (ALBEDO_RES - 1) as Float).max(0.07);
+            array::from_fn(|j| {
+                let cos_theta = (j as Float / (ALBEDO_RES - 1) as Float).max(1e-3);
+                let wo = Vec3::new((1.0 - cos_theta * cos_theta).sqrt(), 0.0, cos_theta);
+
+                // integrate over a grid of cosine weighted directions
+                let n: usize = 48;
+                let mut sum = 0.0;
+                for u in 0..n {
+                    for v in 0..n {
+                        let r = ((u as Float + 0.5) / n as Float).sqrt();
+                        let phi = 2.0 * PI * (v as Float + 0.5) / n as Float;
+                        let wi = Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - r * r).sqrt());
+                        sum += sheen_reflectance(roughness, wo, wi) * PI / wi.z;
+                    }
+                }
+                sum / (n * n) as Float
+            })
+        })
+    });
+
+    let x = roughness.clamp(0.0, 1.0) * (ALBEDO_RES - 1) as Float;
+    let y = cos_theta.clamp(0.0, 1.0) * (ALBEDO_RES - 1) as Float;
+    let (i, j) = (
+        (x as usize).min(ALBEDO_RES - 2),
+        (y as usize).min(ALBEDO_RES - 2),
+    );
+    let (dx, dy) = (x - i as Float, y - j as Float);
+    let lerp = |t: Float, a: Float, b: Float| a + (b - a) * t;
+    lerp(
+        dx,
+        lerp(dy, table[i][j], table[i][j + 1]),
+        lerp(dy, table[i + 1][j], table[i + 1][j + 1]),
+    )
+}
+
+#[must_use]
+pub fn oren_nayar(tex: Arc<dyn Texture>, roughness: Float) -> Arc<OrenNayar> {
+    Arc::new(OrenNayar::new(tex, roughness))
+}
+
+#[must_use]
+pub fn oren_nayar_from_color(albedo: Color, roughness: Float) -> Arc<OrenNayar> {
+    Arc::new(OrenNayar::from_color(albedo, roughness))
+}
@@ -0,0 +1,343 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    hittables::HitRecord,
+    misc::{rand_float, random_cosine_direction},
+    pdfs::Pdf,
+    primitives::{Color, Float, Onb, PathState, Point3, Ray, Vec3, color, consts::PI, vec3},
+    tern,
+    textures::Texture,
+};
+
+use super::{
+    Material, ScatterRecord,
+    microfacet::{TrowbridgeReitz, reflection_pdf},
+};
+
+/// Roughness is kept at least this high, so the specular lobes never become
+/// mirrors that can't be sampled together with the lights.
+const MIN_ROUGHNESS: Float = 0.05;
+
+/// A material following the glTF 2.0 metallic-roughness model, along with
+/// its specular, transmission, ior and emissive strength extensions, so glTF
+/// materials can be used as they are. Every factor is multiplied by its
+/// texture when it has one, as glTF does.
+///
+/// A metallic surface reflects its base color off GGX microfacets. Anything
+/// else is a dielectric, whose microfacets reflect the specular color over
+/// a base that's diffuse or, as much as it's transmissive, lets light
+/// through it as a thin wall does.
+pub struct Principled {
+    base_color: Color,
+    base_color_texture: Option<Arc<dyn Texture>>,
+    metallic: Float,
+    roughness: Float,
+    /// Metalness in the blue channel and roughness in the green.
+    metallic_roughness_texture: Option<Arc<dyn Texture>>,
+    specular: Float,
+    specular_color: Color,
+    specular_color_texture: Option<Arc<dyn Texture>>,
+    transmission: Float,
+    /// Transmission in the red channel.
+    transmission_texture: Option<Arc<dyn Texture>>,
+    emissive: Color,
+    emissive_strength: Float,
+    emissive_texture: Option<Arc<dyn Texture>>,
+    ior: Float,
+}
+
+impl Principled {
+    /// `metallic` and `roughness` are in `[0, 1]`.
+    #[must_use]
+    pub fn new(base_color: Color, metallic: Float, roughness: Float) -> Self {
+        Self {
+            base_color,
+            base_color_texture: None,
+            metallic,
+            roughness,
+            metallic_roughness_texture: None,
+            specular: 1.0,
+            specular_color: color(1.0, 1.0, 1.0),
+            specular_color_texture: None,
+            transmission: 0.0,
+            transmission_texture: None,
+            emissive: Color::default(),
+            emissive_strength: 1.0,
+            emissive_texture: None,
+            ior: 1.5,
+        }
+    }
+
+    #[must_use]
+    pub fn with_base_color_texture(mut self, tex: Arc<dyn Texture>) -> Self {
+        self.base_color_texture = Some(tex);
+        self
+    }
+
+    #[must_use]
+    pub fn with_metallic_roughness_texture(mut self, tex: Arc<dyn Texture>) -> Self {
+        self.metallic_roughness_texture = Some(tex);
+        self
+    }
+
+    /// Scales the specular reflection of the dielectric, and tints it at
+    /// normal incidence.
+    #[must_use]
+    pub fn with_specular(mut self, specular: Float, specular_color: Color) -> Self {
+        self.specular = specular;
+        self.specular_color = specular_color;
+        self
+    }
+
+    #[must_use]
+    pub fn with_specular_color_texture(mut self, tex: Arc<dyn Texture>) -> Self {
+        self.specular_color_texture = Some(tex);
+        self
+    }
+
+    #[must_use]
+    pub fn with_transmission(mut self, transmission: Float) -> Self {
+        self.transmission = transmission;
+        self
+    }
+
+    #[must_use]
+    pub fn with_transmission_texture(mut self, tex: Arc<dyn Texture>) -> Self {
+        self.transmission_texture = Some(tex);
+        self
+    }
+
+    #[must_use]
+    pub fn with_emission(mut self, emissive: Color, strength: Float) -> Self {
+        self.emissive = emissive;
+        self.emissive_strength = strength;
+        self
+    }
+
+    #[must_use]
+    pub fn with_emissive_texture(mut self, tex: Arc<dyn Texture>) -> Self {
+        self.emissive_texture = Some(tex);
+        self
+    }
+
+    #[must_use]
+    pub fn with_ior(mut self, ior: Float) -> Self {
+        self.ior = ior;
+        self
+    }
+
+    /// The parameters at a hit, with the textures applied.
+    fn lobes(&self, rec: &HitRecord) -> Lobes {
+        let sample = |tex: &Option<Arc<dyn Texture>>| {
+            tex.as_ref()
+                .map_or(color(1.0, 1.0, 1.0), |tex| tex.value(rec.u, rec.v, rec.p))
+        };
+
+        let metallic_roughness = sample(&self.metallic_roughness_texture);
+        let roughness = (self.roughness * metallic_roughness.g).max(MIN_ROUGHNESS);
+
+        let r0 = ((self.ior - 1.0) / (self.ior + 1.0)).powi(2);
+        let f0 = (self.specular_color * sample(&self.specular_color_texture) * r0)
+            .map(|c| c.min(1.0))
+            * self.specular;
+
+        Lobes {
+            base: self.base_color * sample(&self.base_color_texture),
+            metallic: self.metallic * metallic_roughness.b,
+            transmission: self.transmission * sample(&self.transmission_texture).r,
+            f0,
+            f90: self.specular,
+            distribution: TrowbridgeReitz::new(roughness, roughness),
+        }
+    }
+}
+
+/// What a `Principled` material is made of at a single point.
+#[derive(Clone, Copy)]
+struct Lobes {
+    base: Color,
+    metallic: Float,
+    transmission: Float,
+    /// Reflectance of the dielectric at normal and grazing incidence.
+    f0: Color,
+    f90: Float,
+    distribution: TrowbridgeReitz,
+}
+
+impl Lobes {
+    fn dielectric_fresnel(&self, cos_theta: Float) -> Color {
+        let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+        self.f0 * (1.0 - weight) + color(self.f90, self.f90, self.f90) * weight
+    }
+
+    fn metal_fresnel(&self, cos_theta: Float) -> Color {
+        let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+        self.base * (1.0 - weight) + color(weight, weight, weight)
+    }
+
+    /// How likely the diffuse, specular and transmission lobes are to be
+    /// sampled when looking from `wo`.
+    fn weights(&self, wo: Vec3) -> [Float; 3] {
+        let specular = max_channel(self.dielectric_fresnel(wo.z));
+        let dielectric = (1.0 - self.metallic) * (1.0 - specular);
+        [
+            dielectric * (1.0 - self.transmission),
+            self.metallic + (1.0 - self.metallic) * specular,
+            dielectric * self.transmission,
+        ]
+    }
+
+    /// The BSDF times the cosine of `wi`, in the local shading frame.
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        if wi.z > 0.0 {
+            let wm = (wo + wi).unit_vector();
+            let specular = self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * wo.z);
+            let cos_theta = wo.dot(wm);
+
+            let fresnel = self.dielectric_fresnel(cos_theta);
+            let diffuse = wi.z / PI * (1.0 - self.transmission) * (1.0 - max_channel(fresnel));
+            let dielectric = self.base * diffuse + fresnel * specular;
+            let metal = self.metal_fresnel(cos_theta) * specular;
+
+            dielectric * (1.0 - self.metallic) + metal * self.metallic
+        } else {
+            // a thin wall, which light leaves as if it were reflected and
+            // then mirrored through the surface
+            let wi = vec3(wi.x, wi.y, -wi.z);
+            let wm = (wo + wi).unit_vector();
+            let specular = self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * wo.z);
+            let fresnel = max_channel(self.dielectric_fresnel(wo.dot(wm)));
+
+            self.base * (specular * (1.0 - fresnel) * self.transmission * (1.0 - self.metallic))
+        }
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> Float {
+        let [diffuse, specular, transmission] = self.weights(wo);
+        if wi.z > 0.0 {
+            diffuse * wi.z / PI + specular * reflection_pdf(self.distribution, wo, wi)
+        } else {
+            let mirrored = vec3(wi.x, wi.y, -wi.z);
+            transmission * reflection_pdf(self.distribution, wo, mirrored)
+        }
+    }
+}
+
+fn max_channel(c: Color) -> Float {
+    c.r.max(c.g).max(c.b)
+}
+
+/// Samples the lobes of a `Principled` material by how much each reflects.
+struct PrincipledPdf {
+    frame: Onb,
+    wo: Vec3,
+    lobes: Lobes,
+}
+
+impl Pdf for PrincipledPdf {
+    fn value(&self, dir: Vec3) -> Float {
+        let wi = self.frame.to_local(dir.unit_vector());
+        self.lobes.pdf(self.wo, wi)
+    }
+
+    fn generate(&self) -> Vec3 {
+        let [diffuse, specular, _] = self.lobes.weights(self.wo);
+        let u = rand_float();
+        if u < diffuse {
+            return self.frame.transform(random_cosine_direction());
+        }
+
+        let wm = self.lobes.distribution.sample_wm(self.wo);
+        let wi = (-self.wo).reflect(wm);
+        if wi.z <= 0.0 {
+            return Vec3::default();
+        }
+
+        let wi = tern!(u < diffuse + specular, wi, vec3(wi.x, wi.y, -wi.z));
+        self.frame.transform(wi)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, _path: &mut PathState) -> Option<ScatterRecord> {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        Some(ScatterRecord::Pdf {
+            attenuation: color(1.0, 1.0, 1.0),
+            pdf: Box::new(PrincipledPdf {
+                frame,
+                wo,
+                lobes: self.lobes(rec),
+            }),
+        })
+    }
+
+    fn emitted(
+        &self,
+        _r: &Ray,
+        _rec: &HitRecord,
+        u: Float,
+        v: Float,
+        p: Point3,
+        path: &PathState,
+    ) -> Color {
+        let tex = self
+            .emissive_texture
+            .as_ref()
+            .map_or(color(1.0, 1.0, 1.0), |tex| tex.value(u, v, p));
+        path.spectrum(self.emissive * tex * self.emissive_strength)
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        _path: &PathState,
+    ) -> Float {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r_in.dir.unit_vector());
+        let wi = frame.to_local(scattered.dir.unit_vector());
+        if wo.z <= 0.0 || rec.is_reflection(scattered.dir) != (wi.z > 0.0) {
+            return 0.0;
+        }
+
+        self.lobes(rec).pdf(wo, wi)
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r_in.dir.unit_vector());
+        let wi = frame.to_local(scattered.dir.unit_vector());
+        if wo.z <= 0.0 || rec.is_reflection(scattered.dir) != (wi.z > 0.0) {
+            return Color::default();
+        }
+
+        path.spectrum(self.lobes(rec).eval(wo, wi))
+    }
+}
+
+impl fmt::Display for Principled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "principled({}, {}, {}, {}, {})",
+            self.base_color, self.metallic, self.roughness, self.transmission, self.emissive
+        )
+    }
+}
+
+#[must_use]
+pub fn principled(base_color: Color, metallic: Float, roughness: Float) -> Arc<Principled> {
+    Arc::new(Principled::new(base_color, metallic, roughness))
+}
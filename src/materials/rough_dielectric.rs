@@ -0,0 +1,185 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    hittables::HitRecord,
+    misc::rand_float,
+    primitives::{Color, Float, Medium, PathState, Ray, color},
+    tern,
+};
+
+use super::{
+    Material, ScatterRecord,
+    microfacet::{
+        MicrofacetDielectricPdf, TrowbridgeReitz, dielectric_pdf, fresnel_dielectric, half_vector,
+        refract,
+    },
+};
+
+/// Glass with microfacets following the GGX distribution, which both
+/// reflect and refract light, as frosted glass does.
+#[derive(Clone)]
+pub struct RoughDielectric {
+    medium: Medium,
+    distribution: TrowbridgeReitz,
+}
+
+impl RoughDielectric {
+    /// `roughness` is in `[0, 1]`, from clear glass to a heavy frost.
+    #[must_use]
+    pub fn new(refraction_index: Float, roughness: Float) -> Self {
+        Self::anisotropic(refraction_index, roughness, roughness)
+    }
+
+    /// A dielectric rough differently along the tangent, `u`, and the
+    /// bitangent, `v`.
+    #[must_use]
+    pub fn anisotropic(refraction_index: Float, roughness_u: Float, roughness_v: Float) -> Self {
+        Self {
+            medium: Medium::clear(refraction_index),
+            distribution: TrowbridgeReitz::new(roughness_u, roughness_v),
+        }
+    }
+
+    /// Colors the inside, so only `color` is let through after light has
+    /// travelled `distance` in it.
+    #[must_use]
+    pub fn with_absorption(mut self, color: Color, distance: Float) -> Self {
+        self.medium = self.medium.with_tint(color, distance);
+        self
+    }
+
+    /// The index of refraction past the surface over the one before it, at
+    /// the hero wavelength when `path` traces them.
+    fn eta(&self, path: &PathState, rec: &HitRecord) -> Float {
+        let wavelength = path.wavelengths.map(|w| w.hero());
+        let outside = path
+            .media
+            .surrounding(self.medium, rec.front_face)
+            .refraction_index_at(wavelength);
+        let inside = self.medium.refraction_index_at(wavelength);
+        tern!(rec.front_face, inside / outside, outside / inside)
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        // every other wavelength would have been bent its own way
+        let surrounding = path.media.surrounding(self.medium, rec.front_face);
+        if self.medium.dispersion.is_some() || surrounding.dispersion.is_some() {
+            path.terminate_secondary();
+        }
+
+        let eta = self.eta(path, rec);
+        if self.distribution.is_smooth() {
+            let n = color(1.0, 1.0, 1.0);
+            let reflected = r.dir.reflect(rec.normal);
+            let (dir, attenuation) = match refract(-r.dir.unit_vector(), rec.normal, eta) {
+                // radiance is squeezed into a smaller cone as it refracts
+                Some(refracted) if rand_float() >= fresnel_dielectric(wo.z, eta) => {
+                    (refracted, n / (eta * eta))
+                }
+                _ => (reflected, n),
+            };
+
+            return Some(ScatterRecord::SkipPdf {
+                attenuation,
+                ray: rec.spawn_ray(dir, r.time),
+            });
+        }
+
+        Some(ScatterRecord::Pdf {
+            attenuation: color(1.0, 1.0, 1.0),
+            pdf: Box::new(MicrofacetDielectricPdf::new(
+                frame,
+                wo,
+                eta,
+                self.distribution,
+            )),
+        })
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Float {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r_in.dir.unit_vector());
+        let wi = frame.to_local(scattered.dir.unit_vector());
+        if rec.is_reflection(scattered.dir) != (wi.z > 0.0) {
+            return 0.0;
+        }
+
+        dielectric_pdf(self.distribution, self.eta(path, rec), wo, wi)
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        let frame = rec.shading_frame();
+        let wo = frame.to_local(-r_in.dir.unit_vector());
+        let wi = frame.to_local(scattered.dir.unit_vector());
+        let eta = self.eta(path, rec);
+        if wo.z <= 0.0 || rec.is_reflection(scattered.dir) != (wi.z > 0.0) {
+            return Color::default();
+        }
+        let Some(wm) = half_vector(wo, wi, eta) else {
+            return Color::default();
+        };
+
+        // the cosine of wi cancels with the one in the BSDF's denominator
+        let reflectance = fresnel_dielectric(wo.dot(wm), eta);
+        let d_g = self.distribution.d(wm) * self.distribution.g(wo, wi);
+        let f = if wi.z > 0.0 {
+            d_g * reflectance / (4.0 * wo.z)
+        } else {
+            let denom = (wi.dot(wm) + wo.dot(wm) / eta).powi(2) * wo.z;
+            d_g * (1.0 - reflectance) * (wi.dot(wm) * wo.dot(wm)).abs() / denom / (eta * eta)
+        };
+        color(f, f, f)
+    }
+
+    fn interior(&self) -> Option<Medium> {
+        Some(self.medium)
+    }
+}
+
+impl fmt::Display for RoughDielectric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rough_dielectric({}, {}, {:?})",
+            self.medium.refraction_index, self.medium.absorption, self.distribution
+        )
+    }
+}
+
+#[must_use]
+pub fn rough_dielectric(refraction_index: Float, roughness: Float) -> Arc<RoughDielectric> {
+    Arc::new(RoughDielectric::new(refraction_index, roughness))
+}
+
+#[must_use]
+pub fn anisotropic_rough_dielectric(
+    refraction_index: Float,
+    roughness_u: Float,
+    roughness_v: Float,
+) -> Arc<RoughDielectric> {
+    Arc::new(RoughDielectric::anisotropic(
+        refraction_index,
+        roughness_u,
+        roughness_v,
+    ))
+}
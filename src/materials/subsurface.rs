@@ -0,0 +1,158 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    hittables::HitRecord,
+    misc::{rand_float, random_cosine_direction},
+    pdfs::CosinePdf,
+    primitives::{Color, Float, Medium, Onb, PathState, Ray, color, consts::PI},
+    tern,
+};
+
+use super::{
+    Material, ScatterRecord,
+    microfacet::{TrowbridgeReitz, fresnel_dielectric, scatter_reflection},
+};
+
+/// Something light gets into and wanders around inside of before coming
+/// back out, often far from where it went in, as with skin, wax, marble and
+/// milk. It's filled with a medium that scatters light, which the camera
+/// follows on a random walk until it leaves through the surface, so the
+/// surface should be closed.
+///
+/// Light is spread out evenly as it crosses the surface, as in "Practical
+/// and Controllable Subsurface Scattering for Production Path Tracing"
+/// (Chiang et al. 2016). Where it comes out doesn't depend on where it went
+/// in much anyway, and it lets lights be sampled from where it leaves.
+pub struct Subsurface {
+    albedo: Color,
+    mean_free_path: Color,
+    medium: Medium,
+    distribution: TrowbridgeReitz,
+}
+
+impl Subsurface {
+    /// Looks `albedo` where it's thick enough, with light travelling about
+    /// `mean_free_path` in each channel between each time it's scattered.
+    /// Longer paths blur away more detail and let more light through thin
+    /// parts. `roughness` is of the surface, in `[0, 1]`.
+    #[must_use]
+    pub fn new(
+        albedo: Color,
+        mean_free_path: Color,
+        refraction_index: Float,
+        roughness: Float,
+    ) -> Self {
+        Self {
+            albedo,
+            mean_free_path,
+            medium: Medium::clear(refraction_index).with_scattering(albedo, mean_free_path),
+            distribution: TrowbridgeReitz::new(roughness, roughness),
+        }
+    }
+
+    /// The index of refraction inside over the one outside.
+    fn eta(&self, path: &PathState, rec: &HitRecord) -> Float {
+        let outside = path
+            .media
+            .surrounding(self.medium, rec.front_face)
+            .refraction_index;
+        self.medium.refraction_index / outside
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        let frame = rec.shading_frame();
+        let eta = self.eta(path, rec);
+
+        if rec.front_face {
+            let cos_theta = frame.to_local(-r.dir.unit_vector()).z;
+            if cos_theta <= 0.0 {
+                return None;
+            }
+
+            // the surface is picked as often as it reflects, which cancels
+            // the light lost to it on the way in
+            let reflectance = fresnel_dielectric(cos_theta, eta);
+            if rand_float() < reflectance {
+                return scatter_reflection(self.distribution, r, rec, reflectance, |cos_theta| {
+                    let f = fresnel_dielectric(cos_theta, eta);
+                    color(f, f, f)
+                });
+            }
+
+            let dir = -frame.transform(random_cosine_direction());
+            return (!rec.is_reflection(dir)).then(|| ScatterRecord::SkipPdf {
+                attenuation: color(1.0, 1.0, 1.0),
+                ray: rec.spawn_ray(dir, r.time),
+            });
+        }
+
+        // light is reflected back in as often as the inside of the surface
+        // reflects light coming at it from every direction, and goes on
+        // wandering
+        if rand_float() < diffuse_reflectance(eta) {
+            return Some(ScatterRecord::SkipPdf {
+                attenuation: color(1.0, 1.0, 1.0),
+                ray: rec.spawn_ray(frame.transform(random_cosine_direction()), r.time),
+            });
+        }
+        Some(ScatterRecord::Pdf {
+            attenuation: color(1.0, 1.0, 1.0),
+            pdf: Box::new(CosinePdf::new(Onb::new(-rec.normal))),
+        })
+    }
+
+    fn scattering_pdf(
+        &self,
+        _r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        _path: &PathState,
+    ) -> Float {
+        if rec.front_face || rec.is_reflection(scattered.dir) {
+            return 0.0;
+        }
+
+        let cos_theta = -rec.normal.dot(scattered.dir.unit_vector());
+        (cos_theta / PI).max(0.0)
+    }
+
+    fn interior(&self) -> Option<Medium> {
+        Some(self.medium)
+    }
+}
+
+/// How much light coming from every direction inside a medium with a
+/// relative index of refraction of `eta` the surface reflects back in, as
+/// fit in "A Practical Model for Subsurface Light Transport" (Jensen et al.
+/// 2001).
+fn diffuse_reflectance(eta: Float) -> Float {
+    let fdr = -1.440 / (eta * eta) + 0.710 / eta + 0.668 + 0.0636 * eta;
+    tern!(eta > 1.0, fdr.clamp(0.0, 1.0), 0.0)
+}
+
+impl fmt::Display for Subsurface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "subsurface({}, {:?}, {}, {:?})",
+            self.albedo, self.mean_free_path, self.medium.refraction_index, self.distribution
+        )
+    }
+}
+
+#[must_use]
+pub fn subsurface(
+    albedo: Color,
+    mean_free_path: Color,
+    refraction_index: Float,
+    roughness: Float,
+) -> Arc<Subsurface> {
+    Arc::new(Subsurface::new(
+        albedo,
+        mean_free_path,
+        refraction_index,
+        roughness,
+    ))
+}
@@ -0,0 +1,110 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    hittables::HitRecord,
+    misc::rand_float,
+    primitives::{Color, Float, PathState, Ray, color},
+};
+
+use super::{Material, ScatterRecord, microfacet::fresnel_dielectric, thin_film::film_reflectance};
+
+/// A sheet of glass so thin it can be a single surface, like a window pane
+/// modeled as a `Quad`. Light passing through is bent one way going in and
+/// back coming out, so it carries on in the same direction, and what bounces
+/// around between its two sides is added to what it reflects and lets
+/// through.
+#[derive(Clone)]
+pub struct ThinDielectric {
+    refraction_index: Float,
+    thickness: Option<Float>,
+}
+
+impl ThinDielectric {
+    #[must_use]
+    pub fn new(refraction_index: Float) -> Self {
+        Self {
+            refraction_index,
+            thickness: None,
+        }
+    }
+
+    /// Makes it `thickness` nanometres thick, thin enough for the light
+    /// reflecting off its two sides to interfere, like a soap bubble.
+    #[must_use]
+    pub fn with_thickness(mut self, thickness: Float) -> Self {
+        self.thickness = Some(thickness);
+        self
+    }
+
+    /// How much of the light hitting it at `cos_theta` to the normal it
+    /// reflects, from either side.
+    fn reflectance(&self, path: &PathState, cos_theta: Float) -> Color {
+        let outside = path.media.current().refraction_index;
+        if let Some(thickness) = self.thickness {
+            return film_reflectance(
+                cos_theta,
+                thickness,
+                [outside, self.refraction_index, outside],
+                path.wavelengths,
+            );
+        }
+
+        // the sum of the light reflected after bouncing back and forth inside
+        // any number of times
+        let reflectance = fresnel_dielectric(cos_theta, self.refraction_index / outside);
+        let transmittance = 1.0 - reflectance;
+        let total = reflectance
+            + transmittance * transmittance * reflectance / (1.0 - reflectance * reflectance);
+        color(total, total, total)
+    }
+}
+
+impl Material for ThinDielectric {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        let cos_theta = -rec.normal.dot(r.dir.unit_vector());
+        if cos_theta <= 0.0 {
+            return None;
+        }
+
+        // reflection is picked as often as it happens on average, and each
+        // channel is weighted by how far off that it is
+        let reflectance = self.reflectance(path, cos_theta.min(1.0));
+        let chance = reflectance.average();
+        let (dir, attenuation) = if rand_float() < chance {
+            (r.dir.reflect(rec.normal), reflectance / chance)
+        } else {
+            let transmittance = color(1.0, 1.0, 1.0) - reflectance;
+            (r.dir, transmittance / (1.0 - chance))
+        };
+
+        Some(ScatterRecord::SkipPdf {
+            attenuation,
+            ray: rec.spawn_ray(dir, r.time),
+        })
+    }
+}
+
+impl fmt::Display for ThinDielectric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.thickness {
+            Some(thickness) => write!(
+                f,
+                "thin_dielectric({}, {thickness}nm)",
+                self.refraction_index
+            ),
+            None => write!(f, "thin_dielectric({})", self.refraction_index),
+        }
+    }
+}
+
+#[must_use]
+pub fn thin_dielectric(refraction_index: Float) -> Arc<ThinDielectric> {
+    Arc::new(ThinDielectric::new(refraction_index))
+}
+
+/// A soap bubble, a film of water `thickness` nanometres thick, which is
+/// usually a few hundred.
+#[must_use]
+pub fn soap_bubble(thickness: Float) -> Arc<ThinDielectric> {
+    Arc::new(ThinDielectric::new(1.33).with_thickness(thickness))
+}
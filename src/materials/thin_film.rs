@@ -0,0 +1,209 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    hittables::HitRecord,
+    misc::rand_float,
+    primitives::{
+        Color, Float, Medium, PathState, Point3, Ray, SampledWavelengths, Vec3, color, consts::PI,
+        reflectance_to_rgb,
+    },
+};
+
+use super::{
+    Material, ScatterRecord,
+    microfacet::{TrowbridgeReitz, scatter_reflection},
+};
+
+/// A film so thin, under a micrometre, that light reflecting off its top and
+/// bottom interferes, like oil on water or the coating on a camera lens.
+/// Which colors are strengthened and which cancel out depends on its
+/// thickness and the angle it's seen at, making it iridescent. Light it
+/// doesn't reflect reaches the base, which is lit by it as in `Coated`.
+pub struct ThinFilm {
+    base: Arc<dyn Material>,
+    thickness: Float,
+    refraction_index: Float,
+    substrate_index: Float,
+}
+
+impl ThinFilm {
+    /// A film `thickness` nanometres thick, on a base with an index of
+    /// refraction of `substrate_index`, which changes which colors it shows.
+    #[must_use]
+    pub fn new(
+        base: Arc<dyn Material>,
+        thickness: Float,
+        refraction_index: Float,
+        substrate_index: Float,
+    ) -> Self {
+        Self {
+            base,
+            thickness,
+            refraction_index,
+            substrate_index,
+        }
+    }
+
+    /// How much of the light crossing the film at `cos_theta` to the normal
+    /// it reflects.
+    fn reflectance(&self, path: &PathState, cos_theta: Float) -> Color {
+        let outside = path.media.current().refraction_index;
+        film_reflectance(
+            cos_theta.abs(),
+            self.thickness,
+            [outside, self.refraction_index, self.substrate_index],
+            path.wavelengths,
+        )
+    }
+}
+
+impl Material for ThinFilm {
+    fn scatter(&self, r: &Ray, rec: &HitRecord, path: &mut PathState) -> Option<ScatterRecord> {
+        let cos_theta = -rec.normal.dot(r.dir.unit_vector());
+        if cos_theta <= 0.0 {
+            return None;
+        }
+
+        // the film is picked as often as it reflects on average, and each
+        // channel is weighted by how far off that it is
+        let reflectance = self.reflectance(path, cos_theta);
+        let chance = reflectance.average();
+        if rand_float() < chance {
+            let smooth = TrowbridgeReitz::new(0.0, 0.0);
+            return scatter_reflection(smooth, r, rec, chance, |cos_theta| {
+                self.reflectance(path, cos_theta)
+            });
+        }
+
+        let through = (color(1.0, 1.0, 1.0) - reflectance) / (1.0 - chance);
+        match self.base.scatter(r, rec, path)? {
+            ScatterRecord::SkipPdf { attenuation, ray } => {
+                let cos_theta = rec.normal.dot(ray.dir.unit_vector());
+                let out = color(1.0, 1.0, 1.0) - self.reflectance(path, cos_theta);
+                Some(ScatterRecord::SkipPdf {
+                    attenuation: attenuation * through * out,
+                    ray,
+                })
+            }
+            // the way out is accounted for in `scattering_color`
+            ScatterRecord::Pdf { attenuation, pdf } => Some(ScatterRecord::Pdf {
+                attenuation: attenuation * through,
+                pdf,
+            }),
+        }
+    }
+
+    fn emitted(
+        &self,
+        r: &Ray,
+        rec: &HitRecord,
+        u: Float,
+        v: Float,
+        p: Point3,
+        path: &PathState,
+    ) -> Color {
+        let cos_theta = rec.normal.dot(r.dir.unit_vector());
+        let through = color(1.0, 1.0, 1.0) - self.reflectance(path, cos_theta);
+        self.base.emitted(r, rec, u, v, p, path) * through
+    }
+
+    fn perturbed_normal(&self, rec: &HitRecord) -> Option<Vec3> {
+        self.base.perturbed_normal(rec)
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Float {
+        self.base.scattering_pdf(r_in, rec, scattered, path)
+    }
+
+    fn scattering_color(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        scattered: &Ray,
+        path: &PathState,
+    ) -> Color {
+        let cos_theta = rec.normal.dot(scattered.dir.unit_vector());
+        let through = color(1.0, 1.0, 1.0) - self.reflectance(path, cos_theta);
+        self.base.scattering_color(r_in, rec, scattered, path) * through
+    }
+
+    fn interior(&self) -> Option<Medium> {
+        self.base.interior()
+    }
+}
+
+impl fmt::Display for ThinFilm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "thin_film({}, {}nm, {}, {})",
+            self.base, self.thickness, self.refraction_index, self.substrate_index
+        )
+    }
+}
+
+#[must_use]
+pub fn thin_film(
+    base: Arc<dyn Material>,
+    thickness: Float,
+    refraction_index: Float,
+    substrate_index: Float,
+) -> Arc<ThinFilm> {
+    Arc::new(ThinFilm::new(
+        base,
+        thickness,
+        refraction_index,
+        substrate_index,
+    ))
+}
+
+/// The light reflected by a film `thickness` nanometres thick, seen at
+/// `cos_theta` to the normal, from the interference of the light off its top
+/// and off its bottom ("Airy" summation). `indices` are the indices of
+/// refraction above the film, of the film, and below it. It's at each of
+/// `wavelengths` when they're traced, and the color it looks in white light
+/// otherwise.
+pub(super) fn film_reflectance(
+    cos_theta: Float,
+    thickness: Float,
+    indices: [Float; 3],
+    wavelengths: Option<SampledWavelengths>,
+) -> Color {
+    let [n1, n2, n3] = indices;
+    let sin2 = |n: Float| (n1 / n).powi(2) * (1.0 - cos_theta * cos_theta);
+    if sin2(n2) >= 1.0 {
+        return color(1.0, 1.0, 1.0);
+    }
+    let cos2 = (1.0 - sin2(n2)).sqrt();
+    let cos3 = (1.0 - sin2(n3)).max(0.0).sqrt();
+
+    // the amplitudes reflected at each interface, for light polarized
+    // perpendicular and parallel to the plane of incidence
+    let s = |ni: Float, ci: Float, nj: Float, cj: Float| (ni * ci - nj * cj) / (ni * ci + nj * cj);
+    let p = |ni: Float, ci: Float, nj: Float, cj: Float| (nj * ci - ni * cj) / (nj * ci + ni * cj);
+    let polarizations = [
+        (s(n1, cos_theta, n2, cos2), s(n2, cos2, n3, cos3)),
+        (p(n1, cos_theta, n2, cos2), p(n2, cos2, n3, cos3)),
+    ];
+
+    let reflectance = |lambda: Float| {
+        let phase = (4.0 * PI * n2 * thickness * cos2 / lambda).cos();
+        polarizations
+            .iter()
+            .map(|(r12, r23)| {
+                let cross = 2.0 * r12 * r23 * phase;
+                (r12 * r12 + r23 * r23 + cross) / (1.0 + (r12 * r23).powi(2) + cross)
+            })
+            .sum::<Float>()
+            / 2.0
+    };
+    wavelengths
+        .map_or_else(|| reflectance_to_rgb(reflectance), |w| w.map(reflectance))
+        .map(|c| c.clamp(0.0, 1.0))
+}
@@ -1,6 +1,6 @@
-use std::{f64::consts::PI, mem};
+use std::mem;
 
-use crate::primitives::{Vec3, vec3};
+use crate::primitives::{Float, Vec3, consts::PI, vec3};
 
 #[macro_export]
 macro_rules! tern {
@@ -10,14 +10,14 @@ macro_rules! tern {
 }
 
 #[must_use]
-pub fn rand_f64() -> f64 {
+pub fn rand_float() -> Float {
     rand::random_range(0.0..1.0)
 }
 
 #[must_use]
 pub fn random_cosine_direction() -> Vec3 {
-    let r1 = rand_f64();
-    let r2 = rand_f64();
+    let r1 = rand_float();
+    let r2 = rand_float();
 
     let phi = 2.0 * PI * r1;
     let x = phi.cos() * r2.sqrt();
@@ -1,9 +1,7 @@
-use std::f64::consts::PI;
-
 use crate::{
     misc::random_cosine_direction,
     pdfs::Pdf,
-    primitives::{Onb, Vec3},
+    primitives::{Float, Onb, Vec3, consts::PI},
 };
 
 pub struct CosinePdf {
@@ -18,7 +16,7 @@ impl CosinePdf {
 }
 
 impl Pdf for CosinePdf {
-    fn value(&self, dir: Vec3) -> f64 {
+    fn value(&self, dir: Vec3) -> Float {
         let cos_theta = dir.unit_vector().dot(self.uvw.w());
         (cos_theta / PI).max(0.0)
     }
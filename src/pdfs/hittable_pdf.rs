@@ -1,7 +1,7 @@
 use crate::{
     hittables::Hittable,
     pdfs::Pdf,
-    primitives::{Point3, Vec3},
+    primitives::{Float, Point3, Vec3},
 };
 
 pub struct HittablePdf<'a> {
@@ -16,7 +16,7 @@ impl<'a> HittablePdf<'a> {
 }
 
 impl Pdf for HittablePdf<'_> {
-    fn value(&self, dir: Vec3) -> f64 {
+    fn value(&self, dir: Vec3) -> Float {
         self.objects.pdf_value(self.origin, dir)
     }
 
@@ -1,4 +1,9 @@
-use crate::{misc::rand_f64, pdfs::Pdf, primitives::Vec3, tern};
+use crate::{
+    misc::rand_float,
+    pdfs::Pdf,
+    primitives::{Float, Vec3},
+    tern,
+};
 
 pub struct MixturePdf<'a>(&'a dyn Pdf, &'a dyn Pdf);
 
@@ -9,11 +14,11 @@ impl<'a> MixturePdf<'a> {
 }
 
 impl Pdf for MixturePdf<'_> {
-    fn value(&self, dir: Vec3) -> f64 {
+    fn value(&self, dir: Vec3) -> Float {
         0.5 * self.0.value(dir) + 0.5 * self.1.value(dir)
     }
 
     fn generate(&self) -> Vec3 {
-        tern!(rand_f64() < 0.5, self.0.generate(), self.1.generate())
+        tern!(rand_float() < 0.5, self.0.generate(), self.1.generate())
     }
 }
@@ -1,11 +1,11 @@
 mod cosine_pdf;
 mod hittable_pdf;
+mod mixture_pdf;
 mod pdf;
 mod sphere_pdf;
-mod mixture_pdf;
 
 pub use cosine_pdf::*;
 pub use hittable_pdf::*;
+pub use mixture_pdf::*;
 pub use pdf::*;
 pub use sphere_pdf::*;
-pub use mixture_pdf::*;
@@ -1,10 +1,12 @@
-use crate::primitives::Vec3;
+use crate::primitives::{Float, Vec3};
 
 pub trait Pdf {
     /// Returns the pdf value for a given direction, or the probability this
     /// given direction was produced by this pdf.
-    fn value(&self, dir: Vec3) -> f64;
+    fn value(&self, dir: Vec3) -> Float;
 
-    /// Generates a random direction.
+    /// Generates a random direction, or a zero vector when the direction
+    /// it sampled was lost, as when a microfacet reflects it into the
+    /// surface.
     fn generate(&self) -> Vec3;
 }
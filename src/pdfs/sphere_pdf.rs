@@ -1,11 +1,12 @@
-use std::f64::consts::PI;
-
-use crate::{pdfs::Pdf, primitives::Vec3};
+use crate::{
+    pdfs::Pdf,
+    primitives::{Float, Vec3, consts::PI},
+};
 
 pub struct SpherePdf;
 
 impl Pdf for SpherePdf {
-    fn value(&self, _dir: Vec3) -> f64 {
+    fn value(&self, _dir: Vec3) -> Float {
         1.0 / (4.0 * PI)
     }
 
@@ -1,8 +1,10 @@
-use std::{fmt, ops::Add};
+use std::{array, fmt, ops::Add};
 
 use crate::tern;
 
-use super::{Interval, Point3, Ray, Vec3, interval};
+use super::{
+    Float, Interval, Lanes, PACKET_WIDTH, PacketMask, Point3, Ray, RayPacket, Vec3, interval,
+};
 
 #[derive(Debug, Clone)]
 pub struct Aabb {
@@ -46,6 +48,14 @@ impl Aabb {
 
     #[must_use]
     pub fn hit(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.clip(r, ray_t).is_some()
+    }
+
+    /// The part of `ray_t` during which `r` is inside the box, if any.
+    #[must_use]
+    pub fn clip(&self, r: &Ray, ray_t: &Interval) -> Option<Interval> {
+        let (mut min, mut max) = (ray_t.min, ray_t.max);
+
         for axis in 0..3 {
             let ax = self.axis_interval(axis);
             let ad = r.dir.axis(axis);
@@ -58,14 +68,45 @@ impl Aabb {
                 ((ax.max - r_orig) / ad, (ax.min - r_orig) / ad)
             );
 
-            let (min, max) = (t0.max(ray_t.min), t1.min(ray_t.max));
+            // narrow the range slab by slab
+            (min, max) = (t0.max(min), t1.min(max));
 
             if max <= min {
-                return false;
+                return None;
             }
         }
 
-        true
+        Some(interval(min, max))
+    }
+
+    /// Slab test for every lane of a packet at once. Each lane is clipped to
+    /// `[t_min, t_max[lane]]`.
+    #[must_use]
+    pub fn hit_packet(&self, rays: &RayPacket, t_min: Float, t_max: &Lanes) -> PacketMask {
+        let mut near = [t_min; PACKET_WIDTH];
+        let mut far = *t_max;
+
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let orig = rays.orig.axis(axis);
+            let inv_dir = rays.inv_dir.axis(axis);
+
+            let t0: Lanes = array::from_fn(|i| (ax.min - orig[i]) * inv_dir[i]);
+            let t1: Lanes = array::from_fn(|i| (ax.max - orig[i]) * inv_dir[i]);
+
+            near = array::from_fn(|i| near[i].max(t0[i].min(t1[i])));
+            far = array::from_fn(|i| far[i].min(t0[i].max(t1[i])));
+        }
+
+        array::from_fn(|i| near[i] < far[i])
+    }
+
+    /// Whether the box is finite, which it isn't for shapes like planes.
+    #[must_use]
+    pub fn is_bounded(&self) -> bool {
+        [&self.x, &self.y, &self.z]
+            .into_iter()
+            .all(|ax| ax.min.is_finite() && ax.max.is_finite())
     }
 
     #[must_use]
@@ -78,7 +119,7 @@ impl Aabb {
             .0 as u8
     }
 
-    const DELTA: f64 = 0.0001;
+    const DELTA: Float = 0.0001;
 
     fn pad_to_minimums(mut self) -> Self {
         if self.x.size() < Self::DELTA {
@@ -1,10 +1,12 @@
-use crate::misc::rand_f64;
+use crate::misc::rand_float;
 use derive_more::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign, derive::Sum};
 use std::{
     fmt,
     ops::{self, Range},
 };
 
+use super::Float;
+
 #[derive(
     Debug,
     Default,
@@ -22,34 +24,34 @@ use std::{
     Sum,
 )]
 pub struct Color {
-    pub r: f64,
-    pub g: f64,
-    pub b: f64,
+    pub r: Float,
+    pub g: Float,
+    pub b: Float,
 }
 
 impl Color {
     #[must_use]
-    pub fn new(r: f64, g: f64, b: f64) -> Self {
+    pub fn new(r: Float, g: Float, b: Float) -> Self {
         Self { r, g, b }
     }
 
     #[must_use]
     pub fn random() -> Self {
-        Self::default().map(|_| rand_f64())
+        Self::default().map(|_| rand_float())
     }
 
     #[must_use]
-    pub fn random_range(range: Range<f64>) -> Self {
+    pub fn random_range(range: Range<Float>) -> Self {
         Self::default().map(|_| rand::random_range(range.clone()))
     }
 
     #[must_use]
-    pub fn tuple(self) -> (f64, f64, f64) {
+    pub fn tuple(self) -> (Float, Float, Float) {
         (self.r, self.g, self.b)
     }
 
     #[must_use]
-    pub fn map(&self, pred: impl Fn(f64) -> f64) -> Self {
+    pub fn map(&self, pred: impl Fn(Float) -> Float) -> Self {
         Self {
             r: pred(self.r),
             g: pred(self.g),
@@ -57,6 +59,12 @@ impl Color {
         }
     }
 
+    /// The mean of the three channels.
+    #[must_use]
+    pub fn average(self) -> Float {
+        (self.r + self.g + self.b) / 3.0
+    }
+
     #[must_use]
     pub fn to_rgb(self) -> (u8, u8, u8) {
         (
@@ -74,7 +82,7 @@ impl fmt::Display for Color {
     }
 }
 
-impl ops::Mul<Color> for f64 {
+impl ops::Mul<Color> for Float {
     type Output = Color;
     fn mul(self, rhs: Color) -> Self::Output {
         rhs * self
@@ -93,11 +101,11 @@ impl ops::Mul<Color> for Color {
     }
 }
 
-fn channel_to_rgb(channel: f64) -> u8 {
+fn channel_to_rgb(channel: Float) -> u8 {
     ((channel * 255.999) as u8).clamp(0, 255)
 }
 
 #[must_use]
-pub fn color(r: f64, g: f64, b: f64) -> Color {
+pub fn color(r: Float, g: Float, b: Float) -> Color {
     Color::new(r, g, b)
 }
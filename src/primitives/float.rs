@@ -0,0 +1,27 @@
+/// The floating point type all of the geometry and color math is done in.
+/// Defaults to `f64`; enabling the `f32` feature halves the size of meshes
+/// and BVH nodes and doubles the width of ray packets, at the cost of
+/// precision in large scenes.
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+/// Mathematical constants for `Float`.
+pub mod consts {
+    #[cfg(feature = "f32")]
+    pub use std::f32::consts::*;
+    #[cfg(not(feature = "f32"))]
+    pub use std::f64::consts::*;
+}
+
+/// Bounds the relative rounding error of `n` chained floating point
+/// operations (Higham's γₙ, as used in PBRT).
+#[must_use]
+// lossless only without the `f32` feature
+#[allow(clippy::cast_lossless)]
+pub fn gamma(n: u32) -> Float {
+    let epsilon = Float::EPSILON * 0.5;
+    let n = n as Float * epsilon;
+    n / (1.0 - n)
+}
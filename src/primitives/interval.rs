@@ -1,49 +1,51 @@
 use std::{fmt, ops::Add};
 
+use super::Float;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Interval {
-    pub min: f64,
-    pub max: f64,
+    pub min: Float,
+    pub max: Float,
 }
 
 impl Interval {
     #[must_use]
-    pub fn new(min: f64, max: f64) -> Self {
+    pub fn new(min: Float, max: Float) -> Self {
         Self { min, max }
     }
 
     #[must_use]
     pub fn full() -> Self {
         Self {
-            min: f64::NEG_INFINITY,
-            max: f64::INFINITY,
+            min: Float::NEG_INFINITY,
+            max: Float::INFINITY,
         }
     }
 
     #[must_use]
     pub fn from_intervals(a: &Interval, b: &Interval) -> Self {
-        let min = f64::min(a.min, b.min);
-        let max = f64::max(a.max, b.max);
+        let min = Float::min(a.min, b.min);
+        let max = Float::max(a.max, b.max);
         Self { min, max }
     }
 
     #[must_use]
-    pub fn size(&self) -> f64 {
+    pub fn size(&self) -> Float {
         self.max - self.min
     }
 
     #[must_use]
-    pub fn contains(&self, x: f64) -> bool {
+    pub fn contains(&self, x: Float) -> bool {
         self.min <= x && x <= self.max
     }
 
     #[must_use]
-    pub fn surrounds(&self, x: f64) -> bool {
+    pub fn surrounds(&self, x: Float) -> bool {
         self.min < x && x < self.max
     }
 
     #[must_use]
-    pub fn expand(&self, delta: f64) -> Self {
+    pub fn expand(&self, delta: Float) -> Self {
         let padding = delta / 2.0;
         Self {
             min: self.min - padding,
@@ -52,9 +54,9 @@ impl Interval {
     }
 }
 
-impl Add<f64> for Interval {
+impl Add<Float> for Interval {
     type Output = Interval;
-    fn add(self, rhs: f64) -> Self::Output {
+    fn add(self, rhs: Float) -> Self::Output {
         Interval::new(self.min + rhs, self.max + rhs)
     }
 }
@@ -62,8 +64,8 @@ impl Add<f64> for Interval {
 impl Default for Interval {
     fn default() -> Self {
         Self {
-            min: f64::INFINITY,
-            max: f64::NEG_INFINITY,
+            min: Float::INFINITY,
+            max: Float::NEG_INFINITY,
         }
     }
 }
@@ -76,6 +78,6 @@ impl fmt::Display for Interval {
 
 // helper initializer to make code look pretty
 #[must_use]
-pub fn interval(min: f64, max: f64) -> Interval {
+pub fn interval(min: Float, max: Float) -> Interval {
     Interval::new(min, max)
 }
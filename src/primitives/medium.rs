@@ -0,0 +1,280 @@
+use crate::misc::rand_float;
+
+use super::{Color, Float, SampledWavelengths, color};
+
+/// The most media a ray can be nested inside of. Any deeper and the
+/// outermost ones are forgotten.
+const MAX_NESTING: usize = 4;
+
+/// How the index of refraction of a medium changes with wavelength, which
+/// splits white light into its colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dispersion {
+    /// `n = a + b / λ²`, with `λ` in micrometres.
+    Cauchy { a: Float, b: Float },
+    /// `n² = 1 + Σ bᵢ λ² / (λ² - cᵢ)`, with `λ` in micrometres.
+    Sellmeier { b: [Float; 3], c: [Float; 3] },
+}
+
+impl Dispersion {
+    /// Borosilicate crown glass, the most common optical glass.
+    pub const BK7: Self = Self::Sellmeier {
+        b: [1.039_612, 0.231_792_3, 1.010_469],
+        c: [0.006_000_699, 0.020_017_91, 103.560_7],
+    };
+    pub const DIAMOND: Self = Self::Sellmeier {
+        b: [0.3306, 4.3356, 0.0],
+        c: [0.030_625, 0.011_236, 0.0],
+    };
+    /// Dense flint glass, which disperses much more than crown glass.
+    pub const SF11: Self = Self::Sellmeier {
+        b: [1.737_597, 0.313_747_3, 1.898_781],
+        c: [0.013_188_71, 0.062_306_81, 155.236_3],
+    };
+
+    /// The index of refraction at `wavelength`, in nanometres.
+    #[must_use]
+    pub fn refraction_index(&self, wavelength: Float) -> Float {
+        let lambda = wavelength / 1000.0;
+        let lambda2 = lambda * lambda;
+        match self {
+            Self::Cauchy { a, b } => a + b / lambda2,
+            Self::Sellmeier { b, c } => (1.0
+                + b.iter()
+                    .zip(c)
+                    .map(|(b, c)| b * lambda2 / (lambda2 - c))
+                    .sum::<Float>())
+            .sqrt(),
+        }
+    }
+}
+
+/// What fills a closed surface, which light passing through is bent by,
+/// absorbed in and scattered around in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Medium {
+    /// The index of refraction when tracing RGB, and at every wavelength
+    /// unless there's `dispersion`.
+    pub refraction_index: Float,
+    /// The fraction of each channel absorbed per unit of distance, as in the
+    /// Beer-Lambert law.
+    pub absorption: Color,
+    /// The fraction of each channel scattered off in a new direction per
+    /// unit of distance.
+    pub scattering: Color,
+    pub dispersion: Option<Dispersion>,
+}
+
+impl Medium {
+    /// The vacuum, or close enough to air, that rays start out in.
+    pub const VACUUM: Self = Self {
+        refraction_index: 1.0,
+        absorption: Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+        },
+        scattering: Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+        },
+        dispersion: None,
+    };
+
+    #[must_use]
+    pub fn new(refraction_index: Float, absorption: Color) -> Self {
+        Self {
+            refraction_index,
+            absorption,
+            scattering: Color::default(),
+            dispersion: None,
+        }
+    }
+
+    /// A clear medium, which absorbs nothing.
+    #[must_use]
+    pub fn clear(refraction_index: Float) -> Self {
+        Self::new(refraction_index, Color::default())
+    }
+
+    /// Absorbs light so only `color` is let through after it has travelled
+    /// `distance`, which is easier to pick than the absorption itself.
+    #[must_use]
+    pub fn with_tint(mut self, color: Color, distance: Float) -> Self {
+        self.absorption = color.map(|c| -c.max(1e-6).ln() / distance);
+        self
+    }
+
+    /// Fills the medium with something that scatters light around, as in
+    /// skin, wax and marble. Light travels `mean_free_path` between each
+    /// time it's scattered or absorbed, in each channel, and the medium looks
+    /// `albedo` once light has wandered back out of a thick enough piece.
+    #[must_use]
+    pub fn with_scattering(mut self, albedo: Color, mean_free_path: Color) -> Self {
+        // the chance of scattering rather than absorbing that makes a slab
+        // look `albedo`, fit in "Practical and Controllable Subsurface
+        // Scattering for Production Path Tracing" (Chiang et al. 2016)
+        let single_scattering = albedo.map(|a| {
+            let a = a.clamp(0.0, 1.0);
+            1.0 - (4.097_12 + 4.208_63 * a - (9.592_17 + 41.680_8 * a + 17.712_6 * a * a).sqrt())
+                .powi(2)
+        });
+        let extinction = mean_free_path.map(|d| 1.0 / d.max(1e-6));
+        self.scattering = extinction * single_scattering;
+        self.absorption = extinction * single_scattering.map(|s| 1.0 - s);
+        self
+    }
+
+    /// Makes the index of refraction follow `dispersion`, taking the one at
+    /// the yellow helium line, 587.6 nm, when tracing RGB.
+    #[must_use]
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Self {
+        self.refraction_index = dispersion.refraction_index(587.6);
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    /// The index of refraction at `wavelength`, in nanometres, or the usual
+    /// one when tracing RGB.
+    #[must_use]
+    pub fn refraction_index_at(&self, wavelength: Option<Float>) -> Float {
+        match (self.dispersion, wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.refraction_index(wavelength),
+            _ => self.refraction_index,
+        }
+    }
+
+    /// The fraction of light that makes it `distance` through the medium, in
+    /// each channel or at each of `wavelengths` when tracing them.
+    #[must_use]
+    pub fn transmittance(&self, distance: Float, wavelengths: Option<SampledWavelengths>) -> Color {
+        if self.absorption == Color::default() {
+            return color(1.0, 1.0, 1.0);
+        }
+        wavelengths
+            .map_or(self.absorption, |w| w.upsample(self.absorption))
+            .map(|a| (-a * distance).exp())
+    }
+
+    /// Samples how far light travels through the medium before it's
+    /// scattered, if that's within `distance`. The distance is sampled for
+    /// `channel`, or the wavelength in that place when tracing them.
+    #[must_use]
+    pub fn sample_scattering(
+        &self,
+        distance: Float,
+        wavelengths: Option<SampledWavelengths>,
+        channel: usize,
+    ) -> ScatteringSample {
+        if self.scattering == Color::default() {
+            return ScatteringSample {
+                distance: None,
+                weight: self.transmittance(distance, wavelengths),
+                pdf: color(1.0, 1.0, 1.0),
+            };
+        }
+
+        let (absorption, scattering) = wavelengths
+            .map_or((self.absorption, self.scattering), |w| {
+                (w.upsample(self.absorption), w.upsample(self.scattering))
+            });
+        let extinction = absorption + scattering;
+
+        let sampled = match channel {
+            0 => extinction.r,
+            1 => extinction.g,
+            _ => extinction.b,
+        };
+        let t = -(1.0 - rand_float()).ln() / sampled;
+
+        if t < distance {
+            let transmittance = extinction.map(|s| (-s * t).exp());
+            ScatteringSample {
+                distance: Some(t),
+                weight: scattering * transmittance,
+                pdf: extinction * transmittance,
+            }
+        } else {
+            let transmittance = extinction.map(|s| (-s * distance).exp());
+            ScatteringSample {
+                distance: None,
+                weight: transmittance,
+                pdf: transmittance,
+            }
+        }
+    }
+}
+
+/// Where light was sampled to scatter in a medium, if it did before reaching
+/// the next surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScatteringSample {
+    pub distance: Option<Float>,
+    /// The light that makes it there, in each channel.
+    pub weight: Color,
+    /// The chance of sampling it, had each channel been the one sampled.
+    /// Paths are weighted by the average of these over all their samples, so
+    /// one channel being unlikely to get somewhere doesn't make it noisy.
+    pub pdf: Color,
+}
+
+impl Default for Medium {
+    fn default() -> Self {
+        Self::VACUUM
+    }
+}
+
+/// The media a ray is inside of, from the outermost to the innermost, which
+/// lets surfaces nested in each other, like a bubble in glass, know what's
+/// on either side of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MediumStack {
+    media: [Medium; MAX_NESTING],
+    len: usize,
+}
+
+impl MediumStack {
+    /// The medium the ray is travelling through.
+    #[must_use]
+    pub fn current(&self) -> Medium {
+        self.len
+            .checked_sub(1)
+            .map_or(Medium::VACUUM, |i| self.media[i])
+    }
+
+    /// The stack after entering `medium`.
+    #[must_use]
+    pub fn pushed(mut self, medium: Medium) -> Self {
+        if self.len == MAX_NESTING {
+            self.media.rotate_left(1);
+            self.len -= 1;
+        }
+        self.media[self.len] = medium;
+        self.len += 1;
+        self
+    }
+
+    /// The stack after leaving `medium`. The innermost matching entry is
+    /// removed even if it isn't the current one, as happens when surfaces
+    /// overlap, and nothing is when the ray never entered it.
+    #[must_use]
+    pub fn popped(mut self, medium: Medium) -> Self {
+        if let Some(i) = self.media[..self.len].iter().rposition(|m| *m == medium) {
+            self.media.copy_within(i + 1..self.len, i);
+            self.len -= 1;
+        }
+        self
+    }
+
+    /// The medium on the other side of a surface enclosing `medium`, from
+    /// the side the ray is on.
+    #[must_use]
+    pub fn surrounding(&self, medium: Medium, front_face: bool) -> Medium {
+        if front_face {
+            self.current()
+        } else {
+            self.popped(medium).current()
+        }
+    }
+}
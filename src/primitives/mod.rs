@@ -1,13 +1,22 @@
 mod aabb;
 mod color;
+mod float;
 mod interval;
+mod medium;
+mod onb;
 mod ray;
+mod ray_packet;
+mod rgb_spectrum;
+mod spectrum;
 mod vec3;
-mod onb;
 
 pub use aabb::*;
 pub use color::*;
+pub use float::*;
 pub use interval::*;
+pub use medium::*;
+pub use onb::*;
 pub use ray::*;
+pub use ray_packet::*;
+pub use spectrum::*;
 pub use vec3::*;
-pub use onb::*;
@@ -21,6 +21,12 @@ impl Onb {
         Self { u, v, w }
     }
 
+    /// Builds a basis from vectors that are already orthonormal.
+    #[must_use]
+    pub fn from_basis(u: Vec3, v: Vec3, w: Vec3) -> Self {
+        Self { u, v, w }
+    }
+
     #[must_use]
     pub fn u(&self) -> Vec3 {
         self.u
@@ -40,4 +46,10 @@ impl Onb {
     pub fn transform(&self, other: Vec3) -> Vec3 {
         other.x * self.u + other.y * self.v + other.z * self.w
     }
+
+    /// The inverse of `transform`, giving `other` in terms of this basis.
+    #[must_use]
+    pub fn to_local(&self, other: Vec3) -> Vec3 {
+        vec3(other.dot(self.u), other.dot(self.v), other.dot(self.w))
+    }
 }
@@ -1,25 +1,97 @@
-use super::{Point3, Vec3};
+use super::{Color, Float, MediumStack, Point3, SampledWavelengths, Vec3, vec3};
 
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct Ray {
     pub orig: Point3,
     pub dir: Vec3,
-    pub time: f64,
+    pub time: Float,
 }
 
 impl Ray {
     #[must_use]
-    pub fn new(orig: Point3, dir: Vec3, time: f64) -> Self {
+    pub fn new(orig: Point3, dir: Vec3, time: Float) -> Self {
         Self { orig, dir, time }
     }
 
     #[must_use]
-    pub fn at(&self, t: f64) -> Point3 {
+    pub fn at(&self, t: Float) -> Point3 {
         self.orig + self.dir * t
     }
 }
 
+/// What a path carries from one ray to the next besides their geometry,
+/// kept by the integrator so rays stay small and transforms don't need to
+/// know about it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PathState {
+    /// The media the path is travelling inside of.
+    pub media: MediumStack,
+    /// The wavelengths the path traces in spectral mode, or `None` when it
+    /// traces RGB.
+    pub wavelengths: Option<SampledWavelengths>,
+}
+
+impl PathState {
+    #[must_use]
+    pub fn with_wavelengths(mut self, wavelengths: SampledWavelengths) -> Self {
+        self.wavelengths = Some(wavelengths);
+        self
+    }
+
+    /// Stops tracing all but the hero wavelength, as a material that splits
+    /// them up has to.
+    pub fn terminate_secondary(&mut self) {
+        self.wavelengths = self
+            .wavelengths
+            .map(SampledWavelengths::terminate_secondary);
+    }
+
+    /// `rgb` as the path sees it, which is upsampled to its wavelengths when
+    /// it traces them.
+    #[must_use]
+    pub fn spectrum(&self, rgb: Color) -> Color {
+        self.wavelengths.map_or(rgb, |w| w.upsample(rgb))
+    }
+}
+
 #[must_use]
-pub fn ray(orig: Point3, dir: Vec3, time: f64) -> Ray {
+pub fn ray(orig: Point3, dir: Vec3, time: Float) -> Ray {
     Ray::new(orig, dir, time)
 }
+
+/// The axis permutation and shear that map a ray's direction onto +z, which
+/// lets triangles be tested with 2D edge functions (Woop et al. 2013). It only
+/// depends on the direction, so it can be computed once per ray.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayShear {
+    pub kx: u8,
+    pub ky: u8,
+    pub kz: u8,
+    pub shear: Vec3,
+}
+
+impl RayShear {
+    #[must_use]
+    pub fn new(dir: Vec3) -> Self {
+        let kz = dir.abs().max_dimension();
+        let kx = (kz + 1) % 3;
+        let ky = (kx + 1) % 3;
+
+        let d = dir.permute(kx, ky, kz);
+
+        Self {
+            kx,
+            ky,
+            kz,
+            shear: vec3(-d.x / d.z, -d.y / d.z, 1.0 / d.z),
+        }
+    }
+
+    /// Moves a point, given relative to the ray origin, into ray space. The z
+    /// component is left unscaled.
+    #[must_use]
+    pub fn apply(&self, p: Vec3) -> Vec3 {
+        let p = p.permute(self.kx, self.ky, self.kz);
+        vec3(p.x + self.shear.x * p.z, p.y + self.shear.y * p.z, p.z)
+    }
+}
@@ -0,0 +1,163 @@
+use std::{array, ops::Sub};
+
+use super::{Float, Ray, RayShear, Vec3, vec3};
+
+/// The number of rays traced together in a `RayPacket`. Twice as many `f32`
+/// lanes fit in a vector register as `f64` ones.
+#[cfg(not(feature = "f32"))]
+pub const PACKET_WIDTH: usize = 4;
+#[cfg(feature = "f32")]
+pub const PACKET_WIDTH: usize = 8;
+
+/// One scalar per lane of a packet.
+pub type Lanes = [Float; PACKET_WIDTH];
+
+/// Which lanes of a packet still take part in a query.
+pub type PacketMask = [bool; PACKET_WIDTH];
+
+/// A structure-of-arrays vector, storing one `Vec3` per lane so that each
+/// component can be processed for every lane at once.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Vec3Packet {
+    pub x: Lanes,
+    pub y: Lanes,
+    pub z: Lanes,
+}
+
+impl Vec3Packet {
+    #[must_use]
+    pub fn from_fn(f: impl Fn(usize) -> Vec3) -> Self {
+        let v: [Vec3; PACKET_WIDTH] = array::from_fn(f);
+        Self {
+            x: v.map(|v| v.x),
+            y: v.map(|v| v.y),
+            z: v.map(|v| v.z),
+        }
+    }
+
+    #[must_use]
+    pub fn splat(v: Vec3) -> Self {
+        Self {
+            x: [v.x; PACKET_WIDTH],
+            y: [v.y; PACKET_WIDTH],
+            z: [v.z; PACKET_WIDTH],
+        }
+    }
+
+    #[must_use]
+    pub fn lane(&self, i: usize) -> Vec3 {
+        vec3(self.x[i], self.y[i], self.z[i])
+    }
+
+    #[must_use]
+    pub fn axis(&self, index: u8) -> &Lanes {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Incorrect index passed to axis"),
+        }
+    }
+
+    #[must_use]
+    pub fn dot(&self, rhs: &Self) -> Lanes {
+        array::from_fn(|i| self.x[i] * rhs.x[i] + self.y[i] * rhs.y[i] + self.z[i] * rhs.z[i])
+    }
+
+    /// Dots every lane with the same vector.
+    #[must_use]
+    pub fn dot_splat(&self, rhs: Vec3) -> Lanes {
+        array::from_fn(|i| self.x[i] * rhs.x + self.y[i] * rhs.y + self.z[i] * rhs.z)
+    }
+
+    #[must_use]
+    pub fn cross(&self, rhs: &Self) -> Self {
+        Self {
+            x: array::from_fn(|i| self.y[i] * rhs.z[i] - self.z[i] * rhs.y[i]),
+            y: array::from_fn(|i| self.z[i] * rhs.x[i] - self.x[i] * rhs.z[i]),
+            z: array::from_fn(|i| self.x[i] * rhs.y[i] - self.y[i] * rhs.x[i]),
+        }
+    }
+
+    /// Crosses every lane with the same vector.
+    #[must_use]
+    pub fn cross_splat(&self, rhs: Vec3) -> Self {
+        self.cross(&Self::splat(rhs))
+    }
+
+    #[must_use]
+    pub fn length_squared(&self) -> Lanes {
+        self.dot(self)
+    }
+
+    #[must_use]
+    pub fn map(&self, pred: impl Fn(Float) -> Float) -> Self {
+        Self {
+            x: self.x.map(&pred),
+            y: self.y.map(&pred),
+            z: self.z.map(&pred),
+        }
+    }
+}
+
+impl Sub for Vec3Packet {
+    type Output = Vec3Packet;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            x: array::from_fn(|i| self.x[i] - rhs.x[i]),
+            y: array::from_fn(|i| self.y[i] - rhs.y[i]),
+            z: array::from_fn(|i| self.z[i] - rhs.z[i]),
+        }
+    }
+}
+
+/// A bundle of `PACKET_WIDTH` rays laid out lane by lane. Coherent rays (for
+/// example, all the samples of one pixel) tend to visit the same BVH nodes,
+/// so testing them together amortizes the traversal and lets the compiler
+/// vectorize the intersection math.
+#[derive(Debug, Clone)]
+pub struct RayPacket {
+    pub orig: Vec3Packet,
+    pub dir: Vec3Packet,
+    pub inv_dir: Vec3Packet,
+    pub shear: [RayShear; PACKET_WIDTH],
+    pub time: Lanes,
+}
+
+impl RayPacket {
+    #[must_use]
+    pub fn new(rays: &[Ray; PACKET_WIDTH]) -> Self {
+        Self::from_parts(
+            Vec3Packet::from_fn(|i| rays[i].orig),
+            Vec3Packet::from_fn(|i| rays[i].dir),
+            array::from_fn(|i| rays[i].time),
+        )
+    }
+
+    #[must_use]
+    pub fn from_parts(orig: Vec3Packet, dir: Vec3Packet, time: Lanes) -> Self {
+        Self {
+            orig,
+            dir,
+            inv_dir: dir.map(|d| 1.0 / d),
+            shear: array::from_fn(|i| RayShear::new(dir.lane(i))),
+            time,
+        }
+    }
+
+    /// Extracts a single lane as a scalar ray.
+    #[must_use]
+    pub fn ray(&self, i: usize) -> Ray {
+        Ray::new(self.orig.lane(i), self.dir.lane(i), self.time[i])
+    }
+
+    /// Returns the point along every lane's ray at the matching `t`.
+    #[must_use]
+    pub fn at(&self, t: &Lanes) -> Vec3Packet {
+        Vec3Packet {
+            x: array::from_fn(|i| self.orig.x[i] + self.dir.x[i] * t[i]),
+            y: array::from_fn(|i| self.orig.y[i] + self.dir.y[i] * t[i]),
+            z: array::from_fn(|i| self.orig.z[i] + self.dir.z[i] * t[i]),
+        }
+    }
+}
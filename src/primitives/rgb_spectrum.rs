@@ -0,0 +1,217 @@
+use std::{array, sync::OnceLock};
+
+use crate::tern;
+
+use super::{
+    Float,
+    spectrum::{CIE_Y_INTEGRAL, LAMBDA_MAX, LAMBDA_MIN, XYZ_TO_RGB, color_matching},
+};
+
+/// How many steps the coefficient table has along each of its axes.
+const RES: usize = 32;
+
+/// The spacing of the wavelengths spectra are integrated over while fitting,
+/// in nanometres.
+const FIT_STEP: Float = 10.0;
+const FIT_SAMPLES: usize = ((LAMBDA_MAX - LAMBDA_MIN) / FIT_STEP) as usize + 1;
+
+/// A smooth spectrum bounded to `[0, 1]`, a sigmoid of a quadratic in the
+/// wavelength, as in "A Low-Dimensional Function Space for Efficient
+/// Spectral Upsampling" (Jakob and Hanika 2019). The quadratic is in the
+/// wavelength rescaled to `[0, 1]` over the traced range.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SigmoidPolynomial {
+    c: [Float; 3],
+}
+
+impl SigmoidPolynomial {
+    /// The spectrum that looks most like `rgb`, whose channels are in
+    /// `[0, 1]`.
+    pub fn from_rgb(rgb: [Float; 3]) -> Self {
+        let table = TABLE.get_or_init(CoefficientTable::fit);
+
+        // the largest channel picks the table and is its z axis, with the
+        // other two relative to it
+        let l = (0..3).fold(0, |l, i| tern!(rgb[i] > rgb[l], i, l));
+        let z = rgb[l];
+        let x = rgb[(l + 1) % 3] / z * (RES - 1) as Float;
+        let y = rgb[(l + 2) % 3] / z * (RES - 1) as Float;
+
+        let xi = (x as usize).min(RES - 2);
+        let yi = (y as usize).min(RES - 2);
+        let zi = table.z_scale.partition_point(|s| *s <= z).clamp(1, RES - 1) - 1;
+        let (dx, dy) = (x - xi as Float, y - yi as Float);
+        let dz = (z - table.z_scale[zi]) / (table.z_scale[zi + 1] - table.z_scale[zi]);
+
+        let c = array::from_fn(|i| {
+            let at = |xo: usize, yo: usize, zo: usize| {
+                table.coefficients[index(l, xi + xo, yi + yo, zi + zo)][i]
+            };
+            let lerp = |t: Float, a: Float, b: Float| a + (b - a) * t;
+            lerp(
+                dz,
+                lerp(
+                    dy,
+                    lerp(dx, at(0, 0, 0), at(1, 0, 0)),
+                    lerp(dx, at(0, 1, 0), at(1, 1, 0)),
+                ),
+                lerp(
+                    dy,
+                    lerp(dx, at(0, 0, 1), at(1, 0, 1)),
+                    lerp(dx, at(0, 1, 1), at(1, 1, 1)),
+                ),
+            )
+        });
+        Self { c }
+    }
+
+    pub fn value(&self, lambda: Float) -> Float {
+        let x = (lambda - LAMBDA_MIN) / (LAMBDA_MAX - LAMBDA_MIN);
+        sigmoid(self.c[0] * x * x + self.c[1] * x + self.c[2])
+    }
+}
+
+fn sigmoid(x: Float) -> Float {
+    if x.is_infinite() {
+        return if x > 0.0 { 1.0 } else { 0.0 };
+    }
+    0.5 + x / (2.0 * (1.0 + x * x).sqrt())
+}
+
+static TABLE: OnceLock<CoefficientTable> = OnceLock::new();
+
+/// The coefficients of the spectra fit to colors over a grid, for each
+/// channel being the largest. They're fit the first time a color is
+/// upsampled, which takes a moment.
+struct CoefficientTable {
+    /// The largest channel at each step along z, spaced more closely near
+    /// black and full brightness where the spectra change quickly.
+    z_scale: [Float; RES],
+    coefficients: Vec<[Float; 3]>,
+}
+
+fn index(l: usize, x: usize, y: usize, z: usize) -> usize {
+    ((l * RES + y) * RES + z) * RES + x
+}
+
+impl CoefficientTable {
+    fn fit() -> Self {
+        let smoothstep = |x: Float| x * x * (3.0 - 2.0 * x);
+        let z_scale = array::from_fn(|k| smoothstep(smoothstep(k as Float / (RES - 1) as Float)));
+
+        // how much each fitting wavelength adds to each channel
+        let weights: [[Float; FIT_SAMPLES]; 3] = array::from_fn(|c| {
+            array::from_fn(|k| {
+                let xyz = color_matching(LAMBDA_MIN + k as Float * FIT_STEP);
+                let rgb: Float = XYZ_TO_RGB[c].iter().zip(xyz).map(|(m, v)| m * v).sum();
+                rgb * FIT_STEP / CIE_Y_INTEGRAL
+            })
+        });
+
+        // fit on the calling thread alone. A rendering thread waiting on
+        // parallel work picks up other pixels meanwhile, which would wait
+        // forever on the table it's still fitting
+        let coefficients = (0..3 * RES)
+            .flat_map(|lj| {
+                let (l, j) = (lj / RES, lj % RES);
+                let mut row = vec![[0.0; 3]; RES * RES];
+                for i in 0..RES {
+                    let x = i as Float / (RES - 1) as Float;
+                    let y = j as Float / (RES - 1) as Float;
+                    let rgb_at = |z: Float| {
+                        let mut rgb = [0.0; 3];
+                        rgb[l] = z;
+                        rgb[(l + 1) % 3] = x * z;
+                        rgb[(l + 2) % 3] = y * z;
+                        rgb
+                    };
+
+                    // each fit starts from the last, walking out from a color
+                    // that's easy to fit
+                    let start = RES / 5;
+                    let mut c = [0.0; 3];
+                    for k in start..RES {
+                        c = fit_coefficients(&weights, rgb_at(z_scale[k]), c);
+                        row[k * RES + i] = c;
+                    }
+                    c = [0.0; 3];
+                    for k in (0..start).rev() {
+                        c = fit_coefficients(&weights, rgb_at(z_scale[k]), c);
+                        row[k * RES + i] = c;
+                    }
+                }
+                row
+            })
+            .collect();
+
+        Self {
+            z_scale,
+            coefficients,
+        }
+    }
+}
+
+/// Refines `c` with Gauss-Newton until its spectrum's color is `rgb`.
+fn fit_coefficients(
+    weights: &[[Float; FIT_SAMPLES]; 3],
+    rgb: [Float; 3],
+    mut c: [Float; 3],
+) -> [Float; 3] {
+    for _ in 0..15 {
+        // the color of the spectrum and how it changes with each coefficient
+        let mut color = [0.0; 3];
+        let mut jacobian = [[0.0; 3]; 3];
+        for k in 0..FIT_SAMPLES {
+            let x = k as Float * FIT_STEP / (LAMBDA_MAX - LAMBDA_MIN);
+            let t = c[0] * x * x + c[1] * x + c[2];
+            let value = sigmoid(t);
+            let slope = 0.5 / (1.0 + t * t).powf(1.5);
+            let basis = [x * x, x, 1.0];
+            for (ch, w) in weights.iter().enumerate() {
+                color[ch] += w[k] * value;
+                for (d, b) in jacobian[ch].iter_mut().zip(basis) {
+                    *d += w[k] * slope * b;
+                }
+            }
+        }
+
+        let residual: [Float; 3] = array::from_fn(|i| rgb[i] - color[i]);
+        if residual.iter().map(|r| r * r).sum::<Float>() < 1e-12 {
+            break;
+        }
+        let Some(step) = solve(jacobian, residual) else {
+            break;
+        };
+        for (c, s) in c.iter_mut().zip(step) {
+            *c += s;
+        }
+
+        // keep the sigmoid from becoming too steep to fit
+        let largest = c.iter().fold(0.0 as Float, |m, c| m.max(c.abs()));
+        if largest > 200.0 {
+            c = c.map(|c| c * 200.0 / largest);
+        }
+    }
+    c
+}
+
+/// Solves `a x = b` by Cramer's rule, or `None` if `a` is singular.
+fn solve(a: [[Float; 3]; 3], b: [Float; 3]) -> Option<[Float; 3]> {
+    let det = |m: [[Float; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+
+    let d = det(a);
+    if d.abs() < 1e-15 {
+        return None;
+    }
+    Some(array::from_fn(|col| {
+        let mut m = a;
+        for (row, b) in m.iter_mut().zip(b) {
+            row[col] = b;
+        }
+        det(m) / d
+    }))
+}
@@ -0,0 +1,215 @@
+use std::array;
+
+use crate::tern;
+
+use super::{Color, Float, color, rgb_spectrum::SigmoidPolynomial};
+
+/// The range of wavelengths that are traced, in nanometres.
+pub const LAMBDA_MIN: Float = 360.0;
+pub const LAMBDA_MAX: Float = 830.0;
+
+/// The integral of the `y` color matching function over the traced range,
+/// which makes a spectrum of ones have a luminance of one.
+pub(super) const CIE_Y_INTEGRAL: Float = 106.922;
+
+/// Maps XYZ to linear sRGB, white balanced from an equal energy spectrum to
+/// D65 with the Bradford transform, so a flat spectrum comes out white.
+pub(super) const XYZ_TO_RGB: [[Float; 3]; 3] = [
+    [3.147_81, -1.662_846, -0.480_574_5],
+    [-0.994_747_3, 1.953_571, 0.039_740_3],
+    [0.063_515_4, -0.214_510_7, 1.151_593],
+];
+
+/// The wavelengths a ray traces in spectral mode, one per channel of a
+/// `Color`, along with the pdf each was sampled with. The first is the hero
+/// wavelength, which decides how light is bent where wavelengths would part
+/// ways ("Hero Wavelength Spectral Sampling", Wilkie et al. 2014).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampledWavelengths {
+    lambda: [Float; 3],
+    pdf: [Float; 3],
+}
+
+impl SampledWavelengths {
+    /// Samples wavelengths evenly spread over the visible spectrum from a
+    /// single `u` in `[0, 1)`, favoring the ones the eye is most sensitive
+    /// to (as in PBRT).
+    #[must_use]
+    pub fn sample_visible(u: Float) -> Self {
+        let lambda: [Float; 3] = array::from_fn(|i| {
+            let u = (u + i as Float / 3.0).fract();
+            538.0 - 138.888_89 * (0.856_910_6 - 1.827_502 * u).atanh()
+        });
+        Self {
+            lambda,
+            pdf: lambda.map(visible_pdf),
+        }
+    }
+
+    #[must_use]
+    pub fn hero(&self) -> Float {
+        self.lambda[0]
+    }
+
+    /// Stops tracing all but the hero wavelength, for when they'd have gone
+    /// their own ways. The light of the others is then carried by the hero.
+    #[must_use]
+    pub fn terminate_secondary(mut self) -> Self {
+        if !self.secondary_terminated() {
+            self.pdf = [self.pdf[0] / 3.0, 0.0, 0.0];
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdf[1] == 0.0
+    }
+
+    /// The value at each wavelength of a smooth spectrum that looks like
+    /// `rgb`. Grays upsample to flat spectra, and brighter colors to the
+    /// spectrum of their hue scaled up.
+    #[must_use]
+    pub fn upsample(&self, rgb: Color) -> Color {
+        let rgb = rgb.map(|c| c.max(0.0));
+        let largest = rgb.r.max(rgb.g).max(rgb.b);
+        let smallest = rgb.r.min(rgb.g).min(rgb.b);
+        if largest - smallest <= largest * Float::EPSILON {
+            return rgb;
+        }
+
+        let scale = largest.max(1.0);
+        let spectrum = SigmoidPolynomial::from_rgb([rgb.r, rgb.g, rgb.b].map(|c| c / scale));
+        self.map(|lambda| spectrum.value(lambda) * scale)
+    }
+
+    /// The value of a spectrum `f` at each wavelength.
+    #[must_use]
+    pub fn map(&self, f: impl Fn(Float) -> Float) -> Color {
+        let [r, g, b] = self.lambda.map(f);
+        color(r, g, b)
+    }
+
+    /// Converts the radiance at each wavelength, as traced from the camera,
+    /// back to linear sRGB.
+    #[must_use]
+    pub fn to_rgb(&self, radiance: Color) -> Color {
+        let radiance = [radiance.r, radiance.g, radiance.b];
+        let mut xyz = [0.0; 3];
+        for ((lambda, pdf), radiance) in self.lambda.into_iter().zip(self.pdf).zip(radiance) {
+            if pdf == 0.0 {
+                continue;
+            }
+            let weight = radiance / (pdf * 3.0 * CIE_Y_INTEGRAL);
+            for (c, m) in xyz.iter_mut().zip(color_matching(lambda)) {
+                *c += m * weight;
+            }
+        }
+
+        let [r, g, b] = XYZ_TO_RGB.map(|row| row.iter().zip(xyz).map(|(m, c)| m * c).sum());
+        color(r, g, b)
+    }
+}
+
+/// The light given off by an ideal black body at `temperature` kelvin, the
+/// color of incandescent bulbs, candles and the sun. It's scaled to a
+/// luminance of one, so lights of any temperature are as bright.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blackbody {
+    temperature: Float,
+    scale: Float,
+    rgb: Color,
+}
+
+impl Blackbody {
+    #[must_use]
+    pub fn new(temperature: Float) -> Self {
+        let wavelengths = (0..=(LAMBDA_MAX - LAMBDA_MIN) as usize).map(|i| LAMBDA_MIN + i as Float);
+        let mut xyz = [0.0; 3];
+        for lambda in wavelengths {
+            let radiance = planck(lambda, temperature);
+            for (c, m) in xyz.iter_mut().zip(color_matching(lambda)) {
+                *c += m * radiance;
+            }
+        }
+
+        let scale = 1.0 / xyz[1];
+        let [r, g, b] = XYZ_TO_RGB.map(|row| row.iter().zip(xyz).map(|(m, c)| m * c * scale).sum());
+        Self {
+            temperature,
+            scale: scale * CIE_Y_INTEGRAL,
+            rgb: color(r, g, b),
+        }
+    }
+
+    #[must_use]
+    pub fn temperature(&self) -> Float {
+        self.temperature
+    }
+
+    /// Its color, or its value at each of `wavelengths` when tracing them.
+    #[must_use]
+    pub fn spectrum(&self, wavelengths: Option<SampledWavelengths>) -> Color {
+        wavelengths.map_or(self.rgb, |w| {
+            w.map(|lambda| planck(lambda, self.temperature) * self.scale)
+        })
+    }
+}
+
+/// The color of a surface that reflects the fraction `f` of the light at
+/// each wavelength, in nanometres, as it looks in white light.
+#[must_use]
+pub fn reflectance_to_rgb(f: impl Fn(Float) -> Float) -> Color {
+    const STEP: Float = 5.0;
+
+    let wavelengths =
+        (0..=((LAMBDA_MAX - LAMBDA_MIN) / STEP) as usize).map(|i| LAMBDA_MIN + i as Float * STEP);
+    let mut xyz = [0.0; 3];
+    for lambda in wavelengths {
+        let reflectance = f(lambda);
+        for (c, m) in xyz.iter_mut().zip(color_matching(lambda)) {
+            *c += m * reflectance * STEP / CIE_Y_INTEGRAL;
+        }
+    }
+
+    let [r, g, b] = XYZ_TO_RGB.map(|row| row.iter().zip(xyz).map(|(m, c)| m * c).sum());
+    color(r, g, b)
+}
+
+/// Planck's law, the radiance of a black body at `temperature` kelvin at
+/// `lambda` nanometres.
+fn planck(lambda: Float, temperature: Float) -> Float {
+    const C: Float = 2.997_925e8;
+    const H: Float = 6.626_07e-34;
+    const KB: Float = 1.380_649e-23;
+
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+    let l = lambda * 1e-9;
+    2.0 * H * C * C / (l.powi(5) * ((H * C / (l * KB * temperature)).exp() - 1.0))
+}
+
+/// The pdf `SampledWavelengths::sample_visible` samples `lambda` with.
+fn visible_pdf(lambda: Float) -> Float {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+    0.003_939_804 / (0.0072 * (lambda - 538.0)).cosh().powi(2)
+}
+
+/// The CIE 1931 color matching functions at `lambda`, using the multi-lobe
+/// fit from "Simple Analytic Approximations to the CIE XYZ Color Matching
+/// Functions" (Wyman et al. 2013).
+pub(super) fn color_matching(lambda: Float) -> [Float; 3] {
+    let g = |mu: Float, sigma_below: Float, sigma_above: Float| {
+        let sigma = tern!(lambda < mu, sigma_below, sigma_above);
+        (-0.5 * ((lambda - mu) / sigma).powi(2)).exp()
+    };
+
+    [
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    ]
+}
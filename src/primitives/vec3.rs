@@ -1,4 +1,4 @@
-use crate::misc::rand_f64;
+use crate::misc::rand_float;
 use crate::tern;
 use derive_more::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use rand::distr::Distribution;
@@ -7,6 +7,8 @@ use std::fmt;
 use std::ops::Mul;
 use std::ops::Range;
 
+use super::Float;
+
 #[derive(
     Debug,
     Clone,
@@ -24,28 +26,28 @@ use std::ops::Range;
     DivAssign,
 )]
 pub struct Vec3 {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+    pub x: Float,
+    pub y: Float,
+    pub z: Float,
 }
 
 impl Vec3 {
     #[must_use]
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
         Self { x, y, z }
     }
 
     #[must_use]
     pub fn random() -> Self {
         Self {
-            x: rand_f64(),
-            y: rand_f64(),
-            z: rand_f64(),
+            x: rand_float(),
+            y: rand_float(),
+            z: rand_float(),
         }
     }
 
     #[must_use]
-    pub fn random_range(range: Range<f64>) -> Self {
+    pub fn random_range(range: Range<Float>) -> Self {
         Self {
             x: rand::random_range(range.clone()),
             y: rand::random_range(range.clone()),
@@ -66,7 +68,7 @@ impl Vec3 {
     #[must_use]
     pub fn random_in_unit_disk() -> Self {
         loop {
-            let p = vec3(rand_f64(), rand_f64(), 0.0);
+            let p = vec3(rand_float(), rand_float(), 0.0);
             if p.length_squared() < 1.0 {
                 return p;
             }
@@ -86,7 +88,7 @@ impl Vec3 {
     }
 
     #[must_use]
-    pub fn axis(&self, index: u8) -> f64 {
+    pub fn axis(&self, index: u8) -> Float {
         match index {
             0 => self.x,
             1 => self.y,
@@ -96,17 +98,44 @@ impl Vec3 {
     }
 
     #[must_use]
-    pub fn length_squared(&self) -> f64 {
+    pub fn abs(&self) -> Self {
+        Self::new(self.x.abs(), self.y.abs(), self.z.abs())
+    }
+
+    #[must_use]
+    pub fn max_component(&self) -> Float {
+        self.x.max(self.y).max(self.z)
+    }
+
+    /// Returns the index of the largest component.
+    #[must_use]
+    pub fn max_dimension(&self) -> u8 {
+        tern!(
+            self.x > self.y,
+            tern!(self.x > self.z, 0, 2),
+            tern!(self.y > self.z, 1, 2)
+        )
+    }
+
+    /// Reorders the components so that the result's x, y and z come from the
+    /// given axes of `self`.
+    #[must_use]
+    pub fn permute(&self, x: u8, y: u8, z: u8) -> Self {
+        Self::new(self.axis(x), self.axis(y), self.axis(z))
+    }
+
+    #[must_use]
+    pub fn length_squared(&self) -> Float {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
 
     #[must_use]
-    pub fn length(&self) -> f64 {
+    pub fn length(&self) -> Float {
         self.length_squared().sqrt()
     }
 
     #[must_use]
-    pub fn dot(&self, rhs: Self) -> f64 {
+    pub fn dot(&self, rhs: Self) -> Float {
         self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
     }
 
@@ -125,7 +154,7 @@ impl Vec3 {
     }
 
     #[must_use]
-    pub fn refract(&self, n: Vec3, eta_i_over_eta_t: f64) -> Vec3 {
+    pub fn refract(&self, n: Vec3, eta_i_over_eta_t: Float) -> Vec3 {
         let cos_theta = (-*self).dot(n).min(1.0);
         let r_out_perp = (*self + n * cos_theta) * eta_i_over_eta_t;
         let r_out_parallel = n * -((1.0 - r_out_perp.length_squared()).abs()).sqrt();
@@ -144,7 +173,7 @@ impl Vec3 {
     }
 }
 
-impl Mul<Vec3> for f64 {
+impl Mul<Vec3> for Float {
     type Output = Vec3;
     fn mul(self, rhs: Vec3) -> Self::Output {
         rhs * self
@@ -167,11 +196,11 @@ pub type Point3 = Vec3;
 
 // helper initializer to make code look pretty
 #[must_use]
-pub fn vec3(x: f64, y: f64, z: f64) -> Vec3 {
+pub fn vec3(x: Float, y: Float, z: Float) -> Vec3 {
     Vec3::new(x, y, z)
 }
 
 #[must_use]
-pub fn point3(x: f64, y: f64, z: f64) -> Point3 {
+pub fn point3(x: Float, y: Float, z: Float) -> Point3 {
     Point3::new(x, y, z)
 }
@@ -4,20 +4,20 @@ use std::{
 };
 
 use crate::{
-    primitives::{Color, Point3},
+    primitives::{Color, Float, Point3},
     tern,
 };
 
 use super::{SolidColor, Texture};
 
 pub struct CheckerTexture {
-    inv_scale: f64,
+    inv_scale: Float,
     even: Arc<dyn Texture>,
     odd: Arc<dyn Texture>,
 }
 
 impl CheckerTexture {
-    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+    pub fn new(scale: Float, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
         Self {
             inv_scale: 1.0 / scale,
             even,
@@ -25,7 +25,7 @@ impl CheckerTexture {
         }
     }
     #[must_use]
-    pub fn from_colors(scale: f64, c1: Color, c2: Color) -> Self {
+    pub fn from_colors(scale: Float, c1: Color, c2: Color) -> Self {
         Self {
             inv_scale: 1.0 / scale,
             even: Arc::new(SolidColor::new(c1)),
@@ -35,7 +35,7 @@ impl CheckerTexture {
 }
 
 impl Texture for CheckerTexture {
-    fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+    fn value(&self, u: Float, v: Float, p: Point3) -> Color {
         let x = (p.x * self.inv_scale).floor() as i32;
         let y = (p.y * self.inv_scale).floor() as i32;
         let z = (p.z * self.inv_scale).floor() as i32;
@@ -59,7 +59,7 @@ impl Display for CheckerTexture {
 }
 
 pub fn checker_texture(
-    scale: f64,
+    scale: Float,
     even: Arc<dyn Texture>,
     odd: Arc<dyn Texture>,
 ) -> Arc<CheckerTexture> {
@@ -67,6 +67,6 @@ pub fn checker_texture(
 }
 
 #[must_use]
-pub fn checker_texture_from_colors(scale: f64, c1: Color, c2: Color) -> Arc<CheckerTexture> {
+pub fn checker_texture_from_colors(scale: Float, c1: Color, c2: Color) -> Arc<CheckerTexture> {
     Arc::new(CheckerTexture::from_colors(scale, c1, c2))
 }
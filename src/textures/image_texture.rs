@@ -4,34 +4,80 @@ use std::{
     sync::Arc,
 };
 
-use crate::primitives::{Color, Point3};
+use crate::primitives::{Color, Float, Point3};
 
 use super::{Texture, rtw_image::RtwImage};
 
+/// What an image texture shows at coordinates outside `[0, 1]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextureWrap {
+    /// Stretches the pixels at the edges out.
+    #[default]
+    Clamp,
+    /// Tiles the image.
+    Repeat,
+    /// Tiles the image, flipping every other tile.
+    MirroredRepeat,
+}
+
+impl TextureWrap {
+    fn apply(self, x: Float) -> Float {
+        match self {
+            Self::Clamp => x.clamp(0.0, 1.0),
+            Self::Repeat => x.rem_euclid(1.0),
+            Self::MirroredRepeat => 1.0 - (x.rem_euclid(2.0) - 1.0).abs(),
+        }
+    }
+}
+
 pub struct ImageTexture {
     image: RtwImage,
+    wrap: (TextureWrap, TextureWrap),
 }
 
 impl ImageTexture {
     #[must_use]
     pub fn new(image: RtwImage) -> Self {
-        Self { image }
+        Self {
+            image,
+            wrap: Default::default(),
+        }
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
-        Ok(Self {
-            image: RtwImage::new(bytes)?,
-        })
+        Ok(Self::new(RtwImage::new(bytes)?))
+    }
+
+    /// An image already decoded to 8-bit RGB or RGBA, `bytes_per_pixel` to
+    /// a pixel and a row at a time from the top.
+    #[must_use]
+    pub fn from_pixels(pixels: Vec<u8>, width: u32, height: u32, bytes_per_pixel: u32) -> Self {
+        Self::new(RtwImage::from_pixels(
+            pixels,
+            width,
+            height,
+            bytes_per_pixel,
+        ))
+    }
+
+    /// Sets what's shown outside `[0, 1]` along `u` and `v`, which clamps
+    /// by default.
+    #[must_use]
+    pub fn with_wrap(mut self, u: TextureWrap, v: TextureWrap) -> Self {
+        self.wrap = (u, v);
+        self
     }
 }
 
 impl Texture for ImageTexture {
-    fn value(&self, u: f64, v: f64, _p: Point3) -> Color {
-        let u = u.clamp(0.0, 1.0);
-        let v = 1.0 - v.clamp(0.0, 1.0);
+    // lossless only without the `f32` feature
+    #[allow(clippy::cast_lossless)]
+    fn value(&self, u: Float, v: Float, _p: Point3) -> Color {
+        let u = self.wrap.0.apply(u);
+        let v = 1.0 - self.wrap.1.apply(v);
 
-        let i = (u * f64::from(self.image.width)) as u32;
-        let j = (v * f64::from(self.image.height)) as u32;
+        let i = ((u * self.image.width as Float) as u32).min(self.image.width - 1);
+        let j = ((v * self.image.height as Float) as u32).min(self.image.height - 1);
 
         self.image.pixel_data(i, j)
     }
@@ -3,18 +3,18 @@ use std::{
     sync::Arc,
 };
 
-use crate::primitives::{Color, Point3, color};
+use crate::primitives::{Color, Float, Point3, color};
 
 use super::{Texture, perlin::Perlin};
 
 pub struct NoiseTexture {
     noise: Perlin,
-    scale: f64,
+    scale: Float,
 }
 
 impl NoiseTexture {
     #[must_use]
-    pub fn new(scale: f64) -> Self {
+    pub fn new(scale: Float) -> Self {
         Self {
             noise: Perlin::new(),
             scale,
@@ -23,7 +23,7 @@ impl NoiseTexture {
 }
 
 impl Texture for NoiseTexture {
-    fn value(&self, _u: f64, _v: f64, p: Point3) -> Color {
+    fn value(&self, _u: Float, _v: Float, p: Point3) -> Color {
         color(0.5, 0.5, 0.5) * (1.0 + (self.scale * p.z + 10.0 * self.noise.turb(p, 7)).sin())
         // color(1.0, 1.0, 1.0) * self.noise.turb(p, 7)
     }
@@ -36,6 +36,6 @@ impl Display for NoiseTexture {
 }
 
 #[must_use]
-pub fn noise_texture(scale: f64) -> Arc<NoiseTexture> {
+pub fn noise_texture(scale: Float) -> Arc<NoiseTexture> {
     Arc::new(NoiseTexture::new(scale))
 }
@@ -2,7 +2,7 @@ use rand::seq::SliceRandom;
 
 use crate::{
     misc::IterExt,
-    primitives::{Point3, Vec3, vec3},
+    primitives::{Float, Point3, Vec3, vec3},
 };
 
 const POINT_COUNT: usize = 256;
@@ -33,7 +33,7 @@ impl Perlin {
         perm
     }
 
-    pub fn noise(&self, p: Point3) -> f64 {
+    pub fn noise(&self, p: Point3) -> Float {
         let u = p.x - p.x.floor();
         let v = p.y - p.y.floor();
         let w = p.z - p.z.floor();
@@ -76,7 +76,7 @@ impl Perlin {
         Self::perlin_interp(c, u, v, w)
     }
 
-    pub fn turb(&self, mut p: Point3, depth: u32) -> f64 {
+    pub fn turb(&self, mut p: Point3, depth: u32) -> Float {
         let mut acc = 0.0;
         let mut weight = 1.0;
 
@@ -89,19 +89,19 @@ impl Perlin {
         acc.abs()
     }
 
-    fn perlin_interp(c: [[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+    fn perlin_interp(c: [[[Vec3; 2]; 2]; 2], u: Float, v: Float, w: Float) -> Float {
         let uu = u * u * (3.0 - 2.0 * u);
         let vv = v * v * (3.0 - 2.0 * v);
         let ww = w * w * (3.0 - 2.0 * w);
         let mut acc = 0.0;
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
-                    let weight_v = vec3(u - i as f64, v - j as f64, w - k as f64);
-                    acc += (i as f64 * uu + (1.0 - i as f64) * (1.0 - uu))
-                        * (j as f64 * vv + (1.0 - j as f64) * (1.0 - vv))
-                        * (k as f64 * ww + (1.0 - k as f64) * (1.0 - ww))
-                        * c[i][j][k].dot(weight_v);
+        for (i, c_i) in c.iter().enumerate() {
+            for (j, c_ij) in c_i.iter().enumerate() {
+                for (k, c_ijk) in c_ij.iter().enumerate() {
+                    let weight_v = vec3(u - i as Float, v - j as Float, w - k as Float);
+                    acc += (i as Float * uu + (1.0 - i as Float) * (1.0 - uu))
+                        * (j as Float * vv + (1.0 - j as Float) * (1.0 - vv))
+                        * (k as Float * ww + (1.0 - k as Float) * (1.0 - ww))
+                        * c_ijk.dot(weight_v);
                 }
             }
         }
@@ -1,6 +1,6 @@
 use png::DecodingError;
 
-use crate::primitives::{Color, color};
+use crate::primitives::{Color, Float, color};
 
 #[derive(Debug)]
 pub struct RtwImage {
@@ -35,6 +35,18 @@ impl RtwImage {
         })
     }
 
+    /// An image already decoded to 8-bit channels, `bytes_per_pixel` to a
+    /// pixel and a row at a time from the top.
+    pub fn from_pixels(pixel_data: Vec<u8>, width: u32, height: u32, bytes_per_pixel: u32) -> Self {
+        Self {
+            pixel_data,
+            width,
+            height,
+            bytes_per_row: bytes_per_pixel * width,
+            bytes_per_pixel,
+        }
+    }
+
     pub fn get_raw_pixel(&self, x: u32, y: u32) -> &[u8] {
         let offset = y * self.bytes_per_row + x * self.bytes_per_pixel;
         &self.pixel_data[offset as usize..(offset + self.bytes_per_pixel) as usize]
@@ -48,9 +60,9 @@ impl RtwImage {
         };
         let scale = 1.0 / 255.0;
         color(
-            f64::from(*r) * scale,
-            f64::from(*g) * scale,
-            f64::from(*b) * scale,
+            Float::from(*r) * scale,
+            Float::from(*g) * scale,
+            Float::from(*b) * scale,
         )
     }
 }
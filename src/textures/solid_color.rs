@@ -3,7 +3,7 @@ use std::{
     sync::Arc,
 };
 
-use crate::primitives::{Color, Point3};
+use crate::primitives::{Color, Float, Point3};
 
 use super::Texture;
 
@@ -19,7 +19,7 @@ impl SolidColor {
 }
 
 impl Texture for SolidColor {
-    fn value(&self, _u: f64, _v: f64, _p: Point3) -> Color {
+    fn value(&self, _u: Float, _v: Float, _p: Point3) -> Color {
         self.albedo
     }
 }
@@ -1,7 +1,7 @@
 use std::fmt;
 
-use crate::primitives::{Color, Point3};
+use crate::primitives::{Color, Float, Point3};
 
 pub trait Texture: Sync + Send + fmt::Display {
-    fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+    fn value(&self, u: Float, v: Float, p: Point3) -> Color;
 }